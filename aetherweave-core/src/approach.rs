@@ -0,0 +1,125 @@
+//! The next closest approach of two bodies, found by running a copy of the simulation ahead.
+
+use crate::{
+    integrator::Integrator,
+    simulation::{BodyId, Simulation},
+    vector::DVec2,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Approach {
+    pub elapsed_seconds: f64,
+    pub distance_m: f64,
+    /// How fast they pass each other.
+    pub speed_m_s: f64,
+    /// Whether they collide and merge rather than pass.
+    pub collision: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ApproachOutcome {
+    Found(Approach),
+    /// They were still drawing apart, or together, when the time to search ran out.
+    TimedOut,
+    /// One of them merged with a third body first.
+    Lost,
+}
+
+/// A search for the first time two bodies stop drawing together, run a little at a time on a
+/// copy of the simulation while the live one carries on.
+pub struct ApproachSearch {
+    simulation: Simulation,
+    bodies: [BodyId; 2],
+    until_seconds: f64,
+    /// The second body's position and velocity relative to the first's, after the last step.
+    last: Option<(DVec2, DVec2)>,
+}
+
+impl ApproachSearch {
+    /// Searches `simulation`, from where it is, for the next closest approach of `bodies` within
+    /// `horizon_s` seconds.
+    pub fn new(simulation: &Simulation, bodies: [BodyId; 2], horizon_s: f64) -> Self {
+        let mut simulation = simulation.duplicate();
+        simulation.trail_duration_s = 0.;
+        let last = relative(&simulation, bodies);
+        Self {
+            until_seconds: simulation.elapsed_seconds() + horizon_s,
+            simulation,
+            bodies,
+            last,
+        }
+    }
+
+    pub fn bodies(&self) -> [BodyId; 2] {
+        self.bodies
+    }
+
+    /// The time searched up to so far, in the simulation's elapsed seconds.
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.simulation.elapsed_seconds()
+    }
+
+    /// Searches on until `keep_going` returns false after a step, returning the outcome once
+    /// there is one.
+    pub fn run_while(
+        &mut self,
+        integrator: Integrator,
+        mut keep_going: impl FnMut() -> bool,
+    ) -> Option<ApproachOutcome> {
+        loop {
+            let Some((position, velocity)) = self.last else {
+                return Some(ApproachOutcome::Lost);
+            };
+            let start = self.simulation.elapsed_seconds();
+            let remaining = self.until_seconds - start;
+            if remaining <= 0. {
+                return Some(ApproachOutcome::TimedOut);
+            }
+            // One step at a time, to look at every one.
+            let mergers = self
+                .simulation
+                .run_for_while(remaining, integrator, || false);
+            let end = self.simulation.elapsed_seconds();
+            if let Some(merger) = mergers.iter().find(|merger| {
+                self.bodies
+                    .iter()
+                    .all(|id| merger.originals.iter().any(|original| original.id == *id))
+            }) {
+                let [a, b] = &merger.originals;
+                return Some(ApproachOutcome::Found(Approach {
+                    elapsed_seconds: end,
+                    distance_m: (b.position - a.position).length(),
+                    speed_m_s: (b.velocity - a.velocity).length(),
+                    collision: true,
+                }));
+            }
+            self.last = relative(&self.simulation, self.bodies);
+            if let Some((next_position, next_velocity)) = self.last {
+                if position.dot(velocity) < 0. && next_position.dot(next_velocity) >= 0. {
+                    // The nearest point of the straight line between the last two separations.
+                    let step = next_position - position;
+                    let fraction = if step.length_sq() > 0. {
+                        (-position.dot(step) / step.length_sq()).clamp(0., 1.)
+                    } else {
+                        0.
+                    };
+                    return Some(ApproachOutcome::Found(Approach {
+                        elapsed_seconds: start + (end - start) * fraction,
+                        distance_m: (position + step * fraction).length(),
+                        speed_m_s: (velocity + (next_velocity - velocity) * fraction).length(),
+                        collision: false,
+                    }));
+                }
+            }
+            if !keep_going() {
+                return None;
+            }
+        }
+    }
+}
+
+/// The second of `bodies`' position and velocity relative to the first's, if both are there.
+fn relative(simulation: &Simulation, [a, b]: [BodyId; 2]) -> Option<(DVec2, DVec2)> {
+    let (a, b) = (simulation.body(a)?, simulation.body(b)?);
+    Some((b.position - a.position, b.velocity - a.velocity))
+}
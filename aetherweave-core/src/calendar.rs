@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// Julian date of the J2000 epoch, 2000-01-01 12:00.
+pub const J2000: f64 = 2_451_545.0;
+/// Julian date of 1970-01-01 00:00.
+const UNIX_EPOCH: f64 = 2_440_587.5;
+pub const SECONDS_PER_DAY: f64 = 86_400.;
+
+/// A proleptic Gregorian calendar date and time of day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    /// 1 to 12.
+    pub month: u32,
+    /// 1 to 31.
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl DateTime {
+    pub fn from_julian_date(julian_date: f64) -> Self {
+        let days = julian_date - UNIX_EPOCH;
+        let whole_days = days.floor();
+        let minutes = ((days - whole_days) * 24. * 60.).floor() as u32;
+        let (year, month, day) = civil_from_days(whole_days as i64);
+        Self {
+            year,
+            month,
+            day,
+            hour: minutes / 60,
+            minute: minutes % 60,
+        }
+    }
+
+    pub fn julian_date(&self) -> f64 {
+        let days = days_from_civil(self.year, self.month, self.day) as f64;
+        UNIX_EPOCH + days + (self.hour * 60 + self.minute) as f64 / (24. * 60.)
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+/// Year, month and day of the given number of days since 1970-01-01.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+/// Number of days since 1970-01-01 of the given date.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = year as i64 - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
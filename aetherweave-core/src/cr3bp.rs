@@ -0,0 +1,266 @@
+//! The circular restricted three-body problem: two primaries going around each other on a
+//! circle, and test particles moving under their pull without pulling back, followed in the
+//! frame turning with the primaries.
+//!
+//! In its normalized units the primaries are a unit of length apart, their masses add up to
+//! one, and they go around once every 2π units of time. The barycenter is at the origin, the
+//! primary at `(-μ, 0)` and the secondary at `(1 - μ, 0)`, where μ is the secondary's share of
+//! the mass.
+
+use crate::{
+    simulation::{Body, BodyId, Simulation, G},
+    vector::{dvec2, DVec2},
+};
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Cr3bp {
+    /// The primary, then the secondary.
+    pub primaries: [BodyId; 2],
+    /// μ, the secondary's share of the primaries' mass.
+    pub mass_ratio: f64,
+    /// How far apart the primaries are: the unit of length.
+    pub distance_m: f64,
+    /// How fast the primaries go around, in radians per second: one over the unit of time.
+    pub angular_velocity: f64,
+}
+
+impl Cr3bp {
+    /// The longest step, in normalized time: about 6,000 a turn.
+    const MAX_STEP: f64 = 1e-3;
+
+    /// The problem for `primary` and `secondary` as far apart as they are now, and the two of
+    /// them put on a circular orbit, counter-clockwise around the origin, with the secondary on
+    /// the positive x axis.
+    pub fn set_up(primary: &Body, secondary: &Body) -> (Self, [Body; 2]) {
+        let [m1, m2] = [primary, secondary].map(|body| body.mass_kg);
+        let distance_m = (secondary.position - primary.position).length();
+        let mass_ratio = m2 / (m1 + m2);
+        let angular_velocity = (G * (m1 + m2) / distance_m.powi(3)).sqrt();
+        let placed = |body: &Body, x: f64| {
+            Body::new(
+                &body.name,
+                body.mass_kg,
+                body.radius_km,
+                dvec2(x * distance_m, 0.),
+                dvec2(0., x * distance_m * angular_velocity),
+                body.color,
+            )
+        };
+        let bodies = [
+            placed(primary, -mass_ratio),
+            placed(secondary, 1. - mass_ratio),
+        ];
+        let problem = Self {
+            primaries: [bodies[0].id, bodies[1].id],
+            mass_ratio,
+            distance_m,
+            angular_velocity,
+        };
+        (problem, bodies)
+    }
+
+    /// Whether both primaries are still in `simulation`.
+    pub fn is_alive(&self, simulation: &Simulation) -> bool {
+        self.primaries
+            .iter()
+            .all(|id| simulation.body(*id).is_some())
+    }
+
+    /// How far the primaries have turned at `elapsed_seconds`, in radians.
+    fn angle(&self, elapsed_seconds: f64) -> f64 {
+        self.angular_velocity * elapsed_seconds
+    }
+
+    /// An inertial position and velocity in meters and m/s, at `elapsed_seconds`, in the
+    /// rotating frame's normalized units.
+    pub fn to_rotating(
+        &self,
+        elapsed_seconds: f64,
+        position: DVec2,
+        velocity: DVec2,
+    ) -> (DVec2, DVec2) {
+        let angle = self.angle(elapsed_seconds);
+        let spin = dvec2(-position.y, position.x) * self.angular_velocity;
+        (
+            position.rotated(-angle) / self.distance_m,
+            (velocity - spin).rotated(-angle) / (self.distance_m * self.angular_velocity),
+        )
+    }
+
+    /// The inverse of [`Self::to_rotating`].
+    pub fn from_rotating(
+        &self,
+        elapsed_seconds: f64,
+        position: DVec2,
+        velocity: DVec2,
+    ) -> (DVec2, DVec2) {
+        let angle = self.angle(elapsed_seconds);
+        let position = position.rotated(angle) * self.distance_m;
+        let spin = dvec2(-position.y, position.x) * self.angular_velocity;
+        (
+            position,
+            velocity.rotated(angle) * (self.distance_m * self.angular_velocity) + spin,
+        )
+    }
+
+    /// The distances to the primary and the secondary from `position`.
+    fn distances(&self, position: DVec2) -> (f64, f64) {
+        let mu = self.mass_ratio;
+        (
+            (position - dvec2(-mu, 0.)).length(),
+            (position - dvec2(1. - mu, 0.)).length(),
+        )
+    }
+
+    /// Ω, the potential of gravity and the centrifugal force together, at `position`.
+    pub fn effective_potential(&self, position: DVec2) -> f64 {
+        let mu = self.mass_ratio;
+        let (r1, r2) = self.distances(position);
+        position.length_sq() / 2. + (1. - mu) / r1 + mu / r2
+    }
+
+    /// The Jacobi constant `2Ω - v²`, the one quantity conserved along a test particle's path.
+    /// The larger it is, the less of the plane the particle can reach.
+    pub fn jacobi_constant(&self, position: DVec2, velocity: DVec2) -> f64 {
+        2. * self.effective_potential(position) - velocity.length_sq()
+    }
+
+    /// A test particle's acceleration in the rotating frame: the gradient of Ω, and the
+    /// Coriolis force.
+    fn acceleration(&self, position: DVec2, velocity: DVec2) -> DVec2 {
+        let mu = self.mass_ratio;
+        let (r1, r2) = self.distances(position);
+        let (k1, k2) = ((1. - mu) / r1.powi(3), mu / r2.powi(3));
+        dvec2(
+            position.x - k1 * (position.x + mu) - k2 * (position.x - 1. + mu) + 2. * velocity.y,
+            position.y - (k1 + k2) * position.y - 2. * velocity.x,
+        )
+    }
+
+    /// One fourth-order Runge-Kutta step of `dt` for a test particle.
+    fn step_particle(&self, position: DVec2, velocity: DVec2, dt: f64) -> (DVec2, DVec2) {
+        let derivative = |(x, v): (DVec2, DVec2)| (v, self.acceleration(x, v));
+        let along =
+            |(x, v): (DVec2, DVec2), (dx, dv): (DVec2, DVec2), h: f64| (x + dx * h, v + dv * h);
+        let state = (position, velocity);
+        let k1 = derivative(state);
+        let k2 = derivative(along(state, k1, dt / 2.));
+        let k3 = derivative(along(state, k2, dt / 2.));
+        let k4 = derivative(along(state, k3, dt));
+        (
+            position + (k1.0 + (k2.0 + k3.0) * 2. + k4.0) * (dt / 6.),
+            velocity + (k1.1 + (k2.1 + k3.1) * 2. + k4.1) * (dt / 6.),
+        )
+    }
+
+    /// Runs `simulation` for `seconds`, backwards if negative, in even steps: the primaries kept
+    /// exactly on their circle, and every other body moved as a test particle in the rotating
+    /// frame. Stops early once `keep_going` returns false after a step, dropping the time left.
+    pub fn run_for_while(
+        &self,
+        simulation: &mut Simulation,
+        seconds: f64,
+        mut keep_going: impl FnMut() -> bool,
+    ) {
+        let normalized = seconds * self.angular_velocity;
+        let steps = (normalized.abs() / Self::MAX_STEP).ceil() as usize;
+        let dt = normalized / steps.max(1) as f64;
+        let mut elapsed = simulation.elapsed_seconds();
+        let trail_duration_s = simulation.trail_duration_s;
+        for _ in 0..steps {
+            let then = elapsed;
+            elapsed += dt / self.angular_velocity;
+            for body in simulation.bodies_mut() {
+                let (position, velocity) = match self.primaries.iter().position(|id| *id == body.id)
+                {
+                    Some(0) => (dvec2(-self.mass_ratio, 0.), DVec2::default()),
+                    Some(_) => (dvec2(1. - self.mass_ratio, 0.), DVec2::default()),
+                    None => {
+                        let (x, v) = self.to_rotating(then, body.position, body.velocity);
+                        self.step_particle(x, v, dt)
+                    }
+                };
+                let (position, velocity) = self.from_rotating(elapsed, position, velocity);
+                body.position = position;
+                body.velocity = velocity;
+                body.record_trail(elapsed, trail_duration_s, dt < 0.);
+            }
+            simulation.set_elapsed_seconds(elapsed);
+            if !keep_going() {
+                break;
+            }
+        }
+    }
+
+    /// Where L1 through L5 are, the collinear ones found by bisection.
+    pub fn lagrange_points(&self) -> [DVec2; 5] {
+        let mu = self.mass_ratio;
+        // The pull along the x axis, which changes sign once between each primary and the next.
+        let pull = |x: f64| self.acceleration(dvec2(x, 0.), DVec2::default()).x;
+        let root = |mut low: f64, mut high: f64| {
+            let rising = pull(high) > pull(low);
+            for _ in 0..100 {
+                let middle = (low + high) / 2.;
+                if (pull(middle) > 0.) == rising {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+            (low + high) / 2.
+        };
+        const MARGIN: f64 = 1e-9;
+        let half_height = 3f64.sqrt() / 2.;
+        [
+            dvec2(root(-mu + MARGIN, 1. - mu - MARGIN), 0.),
+            dvec2(root(1. - mu + MARGIN, 2.), 0.),
+            dvec2(root(-2., -mu - MARGIN), 0.),
+            dvec2(0.5 - mu, half_height),
+            dvec2(0.5 - mu, -half_height),
+        ]
+    }
+
+    /// The zero-velocity curves of Jacobi constant `jacobi_constant`, bounding where a particle
+    /// with it can go, as line segments traced over a grid `cells` across, `half_width` either
+    /// side of the barycenter.
+    pub fn zero_velocity_curves(
+        &self,
+        jacobi_constant: f64,
+        half_width: f64,
+        cells: usize,
+    ) -> Vec<[DVec2; 2]> {
+        let size = 2. * half_width / cells as f64;
+        let corner = |i: usize, j: usize| {
+            dvec2(-half_width + i as f64 * size, -half_width + j as f64 * size)
+        };
+        // Positive where a particle can go, with v² to spare.
+        let values: Vec<Vec<f64>> = (0..=cells)
+            .map(|i| {
+                (0..=cells)
+                    .map(|j| 2. * self.effective_potential(corner(i, j)) - jacobi_constant)
+                    .collect()
+            })
+            .collect();
+        let mut segments = Vec::new();
+        for i in 0..cells {
+            for j in 0..cells {
+                // Marching squares: where the curve crosses each edge of the cell, going round.
+                let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                let crossings: Vec<DVec2> = (0..4)
+                    .filter_map(|edge| {
+                        let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+                        let (value_a, value_b) = (values[a.0][a.1], values[b.0][b.1]);
+                        ((value_a > 0.) != (value_b > 0.)).then(|| {
+                            let fraction = value_a / (value_a - value_b);
+                            corner(a.0, a.1) + (corner(b.0, b.1) - corner(a.0, a.1)) * fraction
+                        })
+                    })
+                    .collect();
+                for pair in crossings.chunks_exact(2) {
+                    segments.push([pair[0], pair[1]]);
+                }
+            }
+        }
+        segments
+    }
+}
@@ -0,0 +1,262 @@
+//! Where the planets are on a given date, from the Keplerian elements and rates in E. M.
+//! Standish's "Keplerian Elements for Approximate Positions of the Major Planets" (JPL), good to
+//! a few arcminutes between 1800 and 2050.
+
+use crate::{calendar::J2000, scenario::elements, simulation::OrbitalElements};
+
+const DAYS_PER_CENTURY: f64 = 36_525.;
+
+/// Mean elements of an orbit around the Sun, relative to the ecliptic and equinox of J2000.
+struct MeanElements {
+    semi_major_axis_au: f64,
+    eccentricity: f64,
+    inclination_deg: f64,
+    mean_longitude_deg: f64,
+    longitude_of_perihelion_deg: f64,
+}
+
+const fn mean(
+    semi_major_axis_au: f64,
+    eccentricity: f64,
+    inclination_deg: f64,
+    mean_longitude_deg: f64,
+    longitude_of_perihelion_deg: f64,
+) -> MeanElements {
+    MeanElements {
+        semi_major_axis_au,
+        eccentricity,
+        inclination_deg,
+        mean_longitude_deg,
+        longitude_of_perihelion_deg,
+    }
+}
+
+/// Each body's elements at J2000, then their change per Julian century.
+const TABLE: [(&str, MeanElements, MeanElements); 10] = [
+    (
+        "Mercury",
+        mean(
+            0.387_099_27,
+            0.205_635_93,
+            7.004_979_02,
+            252.250_323_5,
+            77.457_796_28,
+        ),
+        mean(
+            0.000_000_37,
+            0.000_019_06,
+            -0.005_947_49,
+            149_472.674_111_75,
+            0.160_476_89,
+        ),
+    ),
+    (
+        "Venus",
+        mean(
+            0.723_335_66,
+            0.006_776_72,
+            3.394_676_05,
+            181.979_099_5,
+            131.602_467_18,
+        ),
+        mean(
+            0.000_003_9,
+            -0.000_041_07,
+            -0.000_788_9,
+            58_517.815_387_29,
+            0.002_683_29,
+        ),
+    ),
+    // The table follows the Earth-Moon barycenter, which Earth is never more than 5,000 km from.
+    (
+        "Earth",
+        mean(
+            1.000_002_61,
+            0.016_711_23,
+            -0.000_015_31,
+            100.464_571_66,
+            102.937_681_93,
+        ),
+        mean(
+            0.000_005_62,
+            -0.000_043_92,
+            -0.012_946_68,
+            35_999.372_449_81,
+            0.323_273_64,
+        ),
+    ),
+    (
+        "Mars",
+        mean(
+            1.523_710_34,
+            0.093_394_1,
+            1.849_691_42,
+            -4.553_432_05,
+            -23.943_629_59,
+        ),
+        mean(
+            0.000_018_47,
+            0.000_078_82,
+            -0.008_131_31,
+            19_140.302_684_99,
+            0.444_410_88,
+        ),
+    ),
+    (
+        "Jupiter",
+        mean(
+            5.202_887,
+            0.048_386_24,
+            1.304_396_95,
+            34.396_440_51,
+            14.728_479_83,
+        ),
+        mean(
+            -0.000_116_07,
+            -0.000_132_53,
+            -0.001_837_14,
+            3_034.746_127_75,
+            0.212_526_68,
+        ),
+    ),
+    (
+        "Saturn",
+        mean(
+            9.536_675_94,
+            0.053_861_79,
+            2.485_991_87,
+            49.954_244_23,
+            92.598_878_31,
+        ),
+        mean(
+            -0.001_250_6,
+            -0.000_509_91,
+            0.001_936_09,
+            1_222.493_622_01,
+            -0.418_972_16,
+        ),
+    ),
+    (
+        "Uranus",
+        mean(
+            19.189_164_64,
+            0.047_257_44,
+            0.772_637_83,
+            313.238_104_51,
+            170.954_276_3,
+        ),
+        mean(
+            -0.001_961_76,
+            -0.000_043_97,
+            -0.002_429_39,
+            428.482_027_85,
+            0.408_052_81,
+        ),
+    ),
+    (
+        "Neptune",
+        mean(
+            30.069_922_76,
+            0.008_590_48,
+            1.770_043_47,
+            -55.120_029_69,
+            44.964_762_27,
+        ),
+        mean(
+            0.000_262_91,
+            0.000_051_05,
+            0.000_353_72,
+            218.459_453_25,
+            -0.322_414_64,
+        ),
+    ),
+    (
+        "Pluto",
+        mean(
+            39.482_116_75,
+            0.248_827_3,
+            17.140_012_06,
+            238.929_038_33,
+            224.068_916_29,
+        ),
+        mean(
+            -0.000_315_96,
+            0.000_051_7,
+            0.000_048_18,
+            145.207_805_15,
+            -0.040_629_42,
+        ),
+    ),
+    // Not in the table: its mean motion is its own two-body period, timed from its perihelion of
+    // 2023-10-22, so Jupiter's pull has it drifting by days within a few orbits of then.
+    (
+        "Comet Encke",
+        mean(2.215, 0.8483, 11.78, 81.402, 161.12),
+        mean(0., 0., 0., 10_920.484, 0.),
+    ),
+];
+
+/// The orbit around the Sun of the planet, Pluto or Comet Encke called `name` on `julian_date`,
+/// flattened onto the ecliptic with its periapsis at its longitude of perihelion, or `None` for
+/// any other name.
+pub fn elements_on(name: &str, julian_date: f64) -> Option<OrbitalElements> {
+    let (_, at_j2000, per_century) = TABLE.iter().find(|(body, ..)| *body == name)?;
+    let centuries = (julian_date - J2000) / DAYS_PER_CENTURY;
+    let at =
+        |element: fn(&MeanElements) -> f64| element(at_j2000) + element(per_century) * centuries;
+    let eccentricity = at(|elements| elements.eccentricity);
+    let longitude_of_perihelion_deg = at(|elements| elements.longitude_of_perihelion_deg);
+    let mean_anomaly_deg = at(|elements| elements.mean_longitude_deg) - longitude_of_perihelion_deg;
+    Some(elements(
+        at(|elements| elements.semi_major_axis_au),
+        eccentricity,
+        at(|elements| elements.inclination_deg) as f32,
+        longitude_of_perihelion_deg.rem_euclid(360.),
+        true_anomaly_deg(mean_anomaly_deg, eccentricity),
+    ))
+}
+
+/// The Moon's geocentric ecliptic longitude on `julian_date`, from the largest few periodic
+/// terms of Meeus's "Astronomical Algorithms", chapter 47: within about half a degree.
+pub fn moon_longitude_deg(julian_date: f64) -> f64 {
+    let centuries = (julian_date - J2000) / DAYS_PER_CENTURY;
+    let mean_longitude = 218.316_447_7 + 481_267.881_234_21 * centuries;
+    let [elongation, sun_anomaly, moon_anomaly] = [
+        297.850_192_1 + 445_267.111_403_4 * centuries,
+        357.529_109_2 + 35_999.050_290_9 * centuries,
+        134.963_396_4 + 477_198.867_505_5 * centuries,
+    ]
+    .map(f64::to_radians);
+    let longitude = mean_longitude + 6.289 * moon_anomaly.sin()
+        - 1.274 * (moon_anomaly - 2. * elongation).sin()
+        + 0.658 * (2. * elongation).sin()
+        + 0.214 * (2. * moon_anomaly).sin()
+        - 0.186 * sun_anomaly.sin();
+    longitude.rem_euclid(360.)
+}
+
+/// The true anomaly of a bound orbit `mean_anomaly_deg` along it, solving Kepler's equation
+/// `M = E - e sin E` for the eccentric anomaly by Newton's method.
+pub(crate) fn true_anomaly_deg(mean_anomaly_deg: f64, eccentricity: f64) -> f64 {
+    let mean_anomaly = mean_anomaly_deg
+        .to_radians()
+        .rem_euclid(std::f64::consts::TAU);
+    let mut eccentric_anomaly = if eccentricity > 0.8 {
+        std::f64::consts::PI
+    } else {
+        mean_anomaly
+    };
+    for _ in 0..50 {
+        let correction =
+            (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+                / (1. - eccentricity * eccentric_anomaly.cos());
+        eccentric_anomaly -= correction;
+        if correction.abs() < 1e-12 {
+            break;
+        }
+    }
+    let (sin, cos) = (eccentric_anomaly / 2.).sin_cos();
+    let true_anomaly =
+        2. * ((1. + eccentricity).sqrt() * sin).atan2((1. - eccentricity).sqrt() * cos);
+    true_anomaly.to_degrees().rem_euclid(360.)
+}
@@ -0,0 +1,128 @@
+//! Alignments of bodies as seen from another: conjunctions and oppositions.
+
+use crate::simulation::{BodyId, Simulation};
+use std::f64::consts::PI;
+
+/// `target` as seen from `observer`, against the direction of `reference`, such as Mars seen from
+/// Earth against the Sun.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    pub observer: BodyId,
+    pub target: BodyId,
+    pub reference: BodyId,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlignmentKind {
+    /// The target passes in front of the reference, nearer the observer.
+    InferiorConjunction,
+    /// The target passes behind the reference.
+    Conjunction,
+    /// The target is opposite the reference in the observer's sky.
+    Opposition,
+}
+
+impl AlignmentKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::InferiorConjunction => "Inferior conjunction",
+            Self::Conjunction => "Conjunction",
+            Self::Opposition => "Opposition",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlignmentEvent {
+    pub alignment: Alignment,
+    pub kind: AlignmentKind,
+    /// When it happened, interpolated between the checks either side of it.
+    pub elapsed_seconds: f64,
+}
+
+/// Watches for the [`Alignment`]s it is given coming about, each time it is checked.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct AlignmentWatcher {
+    alignments: Vec<Alignment>,
+    /// The elapsed time and the target's angle from the reference, in radians counter-clockwise,
+    /// at the last check of each alignment whose bodies were all there.
+    #[serde(skip)]
+    last: Vec<Option<(f64, f64)>>,
+}
+
+impl AlignmentWatcher {
+    pub fn alignments(&self) -> &[Alignment] {
+        &self.alignments
+    }
+
+    pub fn watch(&mut self, alignment: Alignment) {
+        if !self.alignments.contains(&alignment) {
+            self.alignments.push(alignment);
+            self.last.push(None);
+        }
+    }
+
+    pub fn unwatch(&mut self, index: usize) {
+        self.alignments.remove(index);
+        self.last.remove(index);
+    }
+
+    /// Forgets the last check, for when the simulation jumps to another state, so that the jump
+    /// isn't taken for the bodies moving through an alignment.
+    pub fn reset(&mut self) {
+        self.last = vec![None; self.alignments.len()];
+    }
+
+    /// The alignments reached since the last check, in either direction of time. A target that
+    /// moves more than a quarter turn across its observer's sky between checks may be missed.
+    pub fn check(&mut self, simulation: &Simulation) -> Vec<AlignmentEvent> {
+        self.last.resize(self.alignments.len(), None);
+        let elapsed = simulation.elapsed_seconds();
+        let mut events = Vec::new();
+        for (alignment, last) in self.alignments.iter().zip(&mut self.last) {
+            let position = |id| simulation.body(id).map(|body| body.position);
+            let (Some(observer), Some(target), Some(reference)) = (
+                position(alignment.observer),
+                position(alignment.target),
+                position(alignment.reference),
+            ) else {
+                *last = None;
+                continue;
+            };
+            let (to_target, to_reference) = (target - observer, reference - observer);
+            let angle = to_reference
+                .cross(to_target)
+                .atan2(to_reference.dot(to_target));
+            if let Some((last_elapsed, last_angle)) = last.filter(|&(time, _)| time != elapsed) {
+                let (was, now) = (last_angle.abs(), angle.abs());
+                let change = wrapped(angle - last_angle).abs();
+                let crossed = last_angle.signum() != angle.signum() && change < PI / 2.;
+                if crossed {
+                    // Through zero, or through half a turn where the angle wraps around.
+                    let (kind, progress) = if was + now < PI {
+                        let kind = if to_target.length() < to_reference.length() {
+                            AlignmentKind::InferiorConjunction
+                        } else {
+                            AlignmentKind::Conjunction
+                        };
+                        (kind, was / change)
+                    } else {
+                        (AlignmentKind::Opposition, (PI - was) / change)
+                    };
+                    events.push(AlignmentEvent {
+                        alignment: *alignment,
+                        kind,
+                        elapsed_seconds: last_elapsed + (elapsed - last_elapsed) * progress,
+                    });
+                }
+            }
+            *last = Some((elapsed, angle));
+        }
+        events
+    }
+}
+
+/// `angle` wrapped into `[-π, π]`.
+fn wrapped(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(2. * PI) - PI
+}
@@ -0,0 +1,231 @@
+use crate::{
+    simulation::{Body, BodyId, Merger, Simulation},
+    vector::DVec2,
+};
+use std::collections::HashMap;
+
+/// The reference frame that positions are drawn and measured in. The physics always runs in the
+/// inertial frame the bodies were set up in; this only moves and turns what is shown.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Frame {
+    #[default]
+    Inertial,
+    /// Centered on the barycenter of the whole system.
+    Barycentric,
+    /// Centered on a body, such as the Sun.
+    Centered(BodyId),
+    /// Centered on the barycenter of a primary and a secondary, and turning with the line
+    /// between them so that it stays along the positive x axis. A pair on a circular orbit then
+    /// stands still, as do their Lagrange points.
+    CoRotating([BodyId; 2]),
+}
+
+/// Takes positions and velocities into a [`Frame`] at one moment: relative to `origin`, which
+/// moves at `velocity`, and turned back by `angle`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTransform {
+    pub origin: DVec2,
+    pub velocity: DVec2,
+    /// In radians, counter-clockwise.
+    pub angle: f64,
+}
+
+impl FrameTransform {
+    fn centered(origin: DVec2, velocity: DVec2) -> Self {
+        Self {
+            origin,
+            velocity,
+            angle: 0.,
+        }
+    }
+
+    /// Turning with the line from `primary` to `secondary`, given as their masses, positions
+    /// and velocities.
+    fn co_rotating(primary: (f64, DVec2, DVec2), secondary: (f64, DVec2, DVec2)) -> Self {
+        let ((m1, x1, v1), (m2, x2, v2)) = (primary, secondary);
+        let offset = x2 - x1;
+        Self {
+            origin: (x1 * m1 + x2 * m2) / (m1 + m2),
+            velocity: (v1 * m1 + v2 * m2) / (m1 + m2),
+            angle: offset.y.atan2(offset.x),
+        }
+    }
+
+    pub fn apply(self, position: DVec2) -> DVec2 {
+        (position - self.origin).rotated(-self.angle)
+    }
+
+    pub fn invert(self, position: DVec2) -> DVec2 {
+        position.rotated(self.angle) + self.origin
+    }
+
+    /// `velocity` as seen moving with the frame's origin and turned with it. A rotating frame's
+    /// own spin is left out, so a body standing still in it doesn't show one.
+    pub fn apply_velocity(self, velocity: DVec2) -> DVec2 {
+        (velocity - self.velocity).rotated(-self.angle)
+    }
+
+    pub fn invert_velocity(self, velocity: DVec2) -> DVec2 {
+        velocity.rotated(self.angle) + self.velocity
+    }
+}
+
+/// Where a [`Frame`] was at the times of the points on the bodies' trails.
+pub enum FrameHistory {
+    Fixed(FrameTransform),
+    /// Moving steadily, as the barycenter does, through `transform` at `seconds`.
+    Drifting {
+        seconds: f64,
+        transform: FrameTransform,
+    },
+    /// Known at the times the bodies it follows left points on their trails, keyed by
+    /// [`f64::to_bits`]. All bodies step together, so their trails share these times.
+    Recorded(HashMap<u64, FrameTransform>),
+}
+
+impl FrameHistory {
+    pub fn at(&self, seconds: f64) -> Option<FrameTransform> {
+        match self {
+            Self::Fixed(transform) => Some(*transform),
+            Self::Drifting {
+                seconds: then,
+                transform,
+            } => Some(FrameTransform {
+                origin: transform.origin + transform.velocity * (seconds - then),
+                ..*transform
+            }),
+            Self::Recorded(transforms) => transforms.get(&seconds.to_bits()).copied(),
+        }
+    }
+}
+
+impl Frame {
+    pub fn name(&self, simulation: &Simulation) -> String {
+        let name = |id: &BodyId| {
+            simulation
+                .body(*id)
+                .map_or_else(|| "?".to_string(), |body| body.name.clone())
+        };
+        match self {
+            Self::Inertial => "Inertial".to_string(),
+            Self::Barycentric => "Barycentric".to_string(),
+            Self::Centered(body) => format!("Centered on {}", name(body)),
+            Self::CoRotating([primary, secondary]) => {
+                format!("Rotating with {} and {}", name(primary), name(secondary))
+            }
+        }
+    }
+
+    /// The bodies the frame follows.
+    pub fn bodies(&self) -> Vec<BodyId> {
+        match self {
+            Self::Inertial | Self::Barycentric => Vec::new(),
+            Self::Centered(body) => vec![*body],
+            Self::CoRotating(pair) => pair.to_vec(),
+        }
+    }
+
+    /// Whether every body the frame follows is in `simulation`.
+    pub fn is_alive(&self, simulation: &Simulation) -> bool {
+        self.bodies()
+            .into_iter()
+            .all(|id| simulation.body(id).is_some())
+    }
+
+    /// Moves the frame onto whatever bodies absorbed those it follows.
+    pub fn follow_merger(&mut self, merger: &Merger) {
+        let follow = |body: &mut BodyId| {
+            if merger.originals.iter().any(|original| original.id == *body) {
+                *body = merger.merged;
+            }
+        };
+        match self {
+            Self::Inertial | Self::Barycentric => {}
+            Self::Centered(body) => follow(body),
+            Self::CoRotating(pair) => pair.iter_mut().for_each(follow),
+        }
+    }
+
+    /// The transform into the frame now, with bodies drawn `step_lag` of the way back through
+    /// their last step as by [`Body::drawn_position`], or `None` if a body it follows is gone.
+    pub fn transform(&self, simulation: &Simulation, step_lag: f64) -> Option<FrameTransform> {
+        let state = |id: &BodyId| {
+            let body = simulation.body(*id)?;
+            Some((body.mass_kg, body.drawn_position(step_lag), body.velocity))
+        };
+        Some(match self {
+            Self::Inertial => FrameTransform::default(),
+            Self::Barycentric => {
+                let bodies = simulation.bodies();
+                let mass_kg: f64 = bodies.iter().map(|body| body.mass_kg).sum();
+                let weighted = |value: &dyn Fn(&Body) -> DVec2| {
+                    bodies.iter().fold(DVec2::ZERO, |sum, body| {
+                        sum + value(body) * (body.mass_kg / mass_kg)
+                    })
+                };
+                if mass_kg <= 0. {
+                    FrameTransform::default()
+                } else {
+                    FrameTransform::centered(
+                        weighted(&|body| body.drawn_position(step_lag)),
+                        weighted(&|body| body.velocity),
+                    )
+                }
+            }
+            Self::Centered(body) => {
+                let (_, position, velocity) = state(body)?;
+                FrameTransform::centered(position, velocity)
+            }
+            Self::CoRotating([primary, secondary]) => {
+                FrameTransform::co_rotating(state(primary)?, state(secondary)?)
+            }
+        })
+    }
+
+    /// Where the frame was when the points on the bodies' trails were left, or `None` if a body
+    /// it follows is gone.
+    pub fn history(&self, simulation: &Simulation) -> Option<FrameHistory> {
+        // Positions along a body's trail, by time.
+        let trail = |id: &BodyId| -> Option<HashMap<u64, DVec2>> {
+            let body = simulation.body(*id)?;
+            let trail = &body.trail;
+            Some(
+                trail
+                    .iter()
+                    .map(|&(seconds, position)| (seconds.to_bits(), position))
+                    .collect(),
+            )
+        };
+        Some(match self {
+            Self::Inertial => FrameHistory::Fixed(FrameTransform::default()),
+            Self::Barycentric => FrameHistory::Drifting {
+                seconds: simulation.elapsed_seconds(),
+                transform: self.transform(simulation, 0.)?,
+            },
+            Self::Centered(body) => FrameHistory::Recorded(
+                trail(body)?
+                    .into_iter()
+                    .map(|(time, position)| (time, FrameTransform::centered(position, DVec2::ZERO)))
+                    .collect(),
+            ),
+            Self::CoRotating([primary, secondary]) => {
+                let mass_kg = |id: &BodyId| Some(simulation.body(*id)?.mass_kg);
+                let (m1, m2) = (mass_kg(primary)?, mass_kg(secondary)?);
+                let secondary_trail = trail(secondary)?;
+                FrameHistory::Recorded(
+                    trail(primary)?
+                        .into_iter()
+                        .filter_map(|(time, x1)| {
+                            let x2 = *secondary_trail.get(&time)?;
+                            let transform = FrameTransform::co_rotating(
+                                (m1, x1, DVec2::ZERO),
+                                (m2, x2, DVec2::ZERO),
+                            );
+                            Some((time, transform))
+                        })
+                        .collect(),
+                )
+            }
+        })
+    }
+}
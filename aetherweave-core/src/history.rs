@@ -0,0 +1,93 @@
+use crate::simulation::{BodyId, Simulation};
+use std::collections::VecDeque;
+
+/// Snapshots of the simulation taken before each edit, to step back and forth through with undo
+/// and redo.
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// Time, in seconds, of the last change recorded by [`Self::record_edit`], while changes
+    /// keep coming.
+    last_edit: Option<f64>,
+}
+
+struct Snapshot {
+    simulation: Simulation,
+    selection: Vec<BodyId>,
+}
+
+impl Snapshot {
+    fn new(simulation: &Simulation, selection: &[BodyId]) -> Self {
+        Self {
+            simulation: simulation.duplicate(),
+            selection: selection.to_vec(),
+        }
+    }
+
+    /// Puts the snapshot in place of `simulation`, returning what it replaced.
+    fn restore(self, simulation: &mut Simulation, selection: &mut Vec<BodyId>) -> Self {
+        let current = Self::new(simulation, selection);
+        *simulation = self.simulation;
+        *selection = self.selection;
+        current
+    }
+}
+
+impl History {
+    /// Snapshots kept for undo; older ones are dropped.
+    pub const MAX_DEPTH: usize = 100;
+    /// Changes recorded by [`Self::record_edit`] less than this many seconds apart are undone
+    /// together.
+    const EDIT_GAP_S: f64 = 1.;
+
+    /// Saves the state before an edit, discarding anything that was undone.
+    pub fn record(&mut self, simulation: &Simulation, selection: &[BodyId]) {
+        if self.undo.len() == Self::MAX_DEPTH {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(Snapshot::new(simulation, selection));
+        self.redo.clear();
+        self.last_edit = None;
+    }
+
+    /// Like [`Self::record`], for a change made at `time` that may be one of a run, such as the
+    /// frames of a drag. Only the state before the first of the run is saved.
+    pub fn record_edit(&mut self, simulation: &Simulation, selection: &[BodyId], time: f64) {
+        if !self
+            .last_edit
+            .is_some_and(|last_edit| time - last_edit < Self::EDIT_GAP_S)
+        {
+            self.record(simulation, selection);
+        }
+        self.last_edit = Some(time);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Restores the state before the last edit, returning whether there was one.
+    pub fn undo(&mut self, simulation: &mut Simulation, selection: &mut Vec<BodyId>) -> bool {
+        let Some(snapshot) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(snapshot.restore(simulation, selection));
+        self.last_edit = None;
+        true
+    }
+
+    /// Reapplies the last undone edit, returning whether there was one.
+    pub fn redo(&mut self, simulation: &mut Simulation, selection: &mut Vec<BodyId>) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push_back(snapshot.restore(simulation, selection));
+        self.last_edit = None;
+        true
+    }
+}
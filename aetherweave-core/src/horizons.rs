@@ -0,0 +1,322 @@
+use crate::{
+    simulation::{remove_net_momentum, Body, G, SUN_MASS_KG},
+    vector::{dvec2, DVec2},
+};
+use ecolor::Color32;
+use std::fmt;
+
+/// Colors given to imported bodies in turn, since Horizons doesn't say what anything looks like.
+const COLORS: [Color32; 8] = [
+    Color32::LIGHT_BLUE,
+    Color32::LIGHT_RED,
+    Color32::LIGHT_GREEN,
+    Color32::YELLOW,
+    Color32::KHAKI,
+    Color32::LIGHT_GRAY,
+    Color32::BROWN,
+    Color32::WHITE,
+];
+
+/// Stands in for the mass of a target whose header doesn't give one, such as a spacecraft, so
+/// that it hardly pulls on anything else.
+const UNKNOWN_MASS_KG: f64 = 1.;
+/// Assumes an Earth-like size when the header doesn't say.
+const UNKNOWN_RADIUS_KM: f32 = 6_371.;
+const AU_M: f64 = 1.495_978_707e11;
+const SECONDS_PER_DAY: f64 = 86_400.;
+
+#[derive(Debug)]
+pub enum HorizonsError {
+    /// There was no `$$SOE` … `$$EOE` table of states.
+    NoStates,
+    /// The table of `target` had no column of this name.
+    MissingColumn {
+        target: String,
+        column: &'static str,
+    },
+    /// The first row of the table of `target` had no value in this column.
+    MissingValue {
+        target: String,
+        column: &'static str,
+    },
+    InvalidNumber {
+        target: String,
+        text: String,
+    },
+}
+
+impl fmt::Display for HorizonsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoStates => write!(
+                f,
+                "No table of states between $$SOE and $$EOE. Is this a Horizons vector table?"
+            ),
+            Self::MissingColumn { target, column } => {
+                write!(f, "The table for \"{target}\" has no {column} column")
+            }
+            Self::MissingValue { target, column } => {
+                write!(f, "The first state of \"{target}\" has no {column}")
+            }
+            Self::InvalidNumber { target, text } => {
+                write!(
+                    f,
+                    "Couldn't read \"{text}\" in the table for \"{target}\" as a number"
+                )
+            }
+        }
+    }
+}
+
+/// The first state in one target's table, in meters and m/s.
+struct Target {
+    name: String,
+    /// Julian date (TDB) of the state.
+    epoch: Option<f64>,
+    mass_kg: Option<f64>,
+    radius_km: Option<f64>,
+    position: [f64; 3],
+    velocity: Option<[f64; 3]>,
+    /// The body at the center of the table's coordinates, such as `Sun` or `Earth`.
+    center: Option<String>,
+}
+
+/// Bodies at the states in a JPL Horizons vector table, in CSV format (as from the web app with
+/// "CSV format" checked, or `CSV_FORMAT=YES`). A file may hold the output for several targets one
+/// after another; each becomes a body at the first state in its table. Names, masses and radii
+/// are read from the headers, which have them for most planets and moons.
+///
+/// The crate is two-dimensional, so each state is projected onto the ecliptic plane, keeping the
+/// tilt of its orbit as the body's drawing inclination. A target without velocity columns is put
+/// on a circular orbit around the Sun at the origin. For heliocentric tables that don't include
+/// the Sun itself, a Sun is added at the origin, moving to cancel the system's net momentum.
+pub fn load_horizons(csv: &str) -> Result<Vec<Body>, HorizonsError> {
+    let targets = parse(csv)?;
+    let mut bodies: Vec<_> = targets
+        .iter()
+        .zip(COLORS.iter().cycle())
+        .map(|(target, &color)| target.body(color))
+        .collect();
+    let has_sun = targets.iter().any(|target| target.name == "Sun");
+    let heliocentric = |target: &Target| target.center.as_deref() == Some("Sun");
+    if !has_sun && targets.iter().all(heliocentric) {
+        let sun = Body::new(
+            "Sun",
+            SUN_MASS_KG,
+            696_340.,
+            DVec2::ZERO,
+            DVec2::ZERO,
+            Color32::GOLD,
+        );
+        bodies.insert(0, sun);
+        remove_net_momentum(&mut bodies);
+    }
+    Ok(bodies)
+}
+
+/// A body from a Horizons vector table, positioned relative to the center of its table.
+pub struct HorizonsTarget {
+    pub body: Body,
+    /// The name of the body at the center, such as `Sun` or `Earth`, if the header gives one.
+    pub center: Option<String>,
+}
+
+/// The bodies in a Horizons vector table as [`load_horizons`] reads them, but without a Sun
+/// added, so that they can be added around their centers in another simulation.
+pub fn horizons_targets(csv: &str) -> Result<Vec<HorizonsTarget>, HorizonsError> {
+    Ok(parse(csv)?
+        .into_iter()
+        .zip(COLORS.iter().cycle())
+        .map(|(target, &color)| HorizonsTarget {
+            body: target.body(color),
+            center: target.center,
+        })
+        .collect())
+}
+
+/// The Julian date (TDB) of the first state in a Horizons vector table, if it has one.
+pub fn horizons_epoch(csv: &str) -> Option<f64> {
+    parse(csv).ok()?.first()?.epoch
+}
+
+fn parse(csv: &str) -> Result<Vec<Target>, HorizonsError> {
+    let targets: Vec<_> = csv
+        .split("$$EOE")
+        .filter_map(|output| output.split_once("$$SOE"))
+        .map(|(header, table)| Target::parse(header, table))
+        .collect::<Result<_, _>>()?;
+    if targets.is_empty() {
+        return Err(HorizonsError::NoStates);
+    }
+    Ok(targets)
+}
+
+impl Target {
+    fn parse(header: &str, table: &str) -> Result<Self, HorizonsError> {
+        let name = header_field(header, "Target body name:")
+            .map_or("Target", body_name)
+            .to_string();
+        let center =
+            header_field(header, "Center body name:").map(|center| body_name(center).to_string());
+        // Given as e.g. `KM-S` or `AU-D`.
+        let (meters, seconds) = header_field(header, "Output units")
+            .and_then(|units| units.trim_start_matches(':').trim().split_once('-'))
+            .map_or((1e3, 1.), |(distance, time)| {
+                let meters = if distance.starts_with("AU") {
+                    AU_M
+                } else {
+                    1e3
+                };
+                let seconds = if time.starts_with('D') {
+                    SECONDS_PER_DAY
+                } else {
+                    1.
+                };
+                (meters, seconds)
+            });
+        let columns: Vec<_> = header
+            .lines()
+            .map(|line| line.split(',').map(str::trim).collect::<Vec<_>>())
+            .filter(|columns| columns.contains(&"X"))
+            .last()
+            .ok_or_else(|| HorizonsError::MissingColumn {
+                target: name.clone(),
+                column: "X",
+            })?;
+        let row: Vec<_> = table
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let value = |column: &'static str| -> Result<Option<f64>, HorizonsError> {
+            let Some(index) = columns.iter().position(|&name| name == column) else {
+                return Ok(None);
+            };
+            match row.get(index).filter(|text| !text.is_empty()) {
+                None => Ok(None),
+                Some(text) => text
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| HorizonsError::InvalidNumber {
+                        target: name.clone(),
+                        text: text.to_string(),
+                    }),
+            }
+        };
+        let required = |column| {
+            value(column)?.ok_or_else(|| HorizonsError::MissingValue {
+                target: name.clone(),
+                column,
+            })
+        };
+        let position = [
+            required("X")?,
+            required("Y")?,
+            value("Z")?.unwrap_or_default(),
+        ];
+        let velocity = match (value("VX")?, value("VY")?) {
+            (Some(vx), Some(vy)) => Some([vx, vy, value("VZ")?.unwrap_or_default()]),
+            _ => None,
+        };
+        Ok(Self {
+            epoch: value("JDTDB")?,
+            mass_kg: mass_kg(header),
+            radius_km: physical_data(header, |label| {
+                let label = label.to_lowercase();
+                label.contains("radius") && label.contains("km")
+            })
+            .map(|(_, radius_km)| radius_km),
+            position: position.map(|x| x * meters),
+            velocity: velocity.map(|v| v.map(|v| v * meters / seconds)),
+            center,
+            name,
+        })
+    }
+
+    fn body(&self, color: Color32) -> Body {
+        let [x, y, z] = self.position;
+        let position = dvec2(x, y);
+        let (velocity, inclination_deg) = match self.velocity {
+            Some([vx, vy, vz]) => {
+                // The tilt of the orbit's angular momentum from the ecliptic pole, folded so that
+                // a retrograde orbit, which already runs clockwise in the plane, tilts as little.
+                let h = [y * vz - z * vy, z * vx - x * vz, x * vy - y * vx];
+                let inclination = h[0].hypot(h[1]).atan2(h[2]).to_degrees();
+                (dvec2(vx, vy), inclination.min(180. - inclination) as f32)
+            }
+            None if position == DVec2::ZERO => (DVec2::ZERO, 0.),
+            None => {
+                let distance = position.length();
+                let speed = (G * SUN_MASS_KG / distance).sqrt();
+                (dvec2(-y, x) / distance * speed, 0.)
+            }
+        };
+        Body::new(
+            &self.name,
+            self.mass_kg.unwrap_or(UNKNOWN_MASS_KG),
+            self.radius_km.unwrap_or(UNKNOWN_RADIUS_KM as f64) as f32,
+            position,
+            velocity,
+            color,
+        )
+        .inclined(inclination_deg)
+    }
+}
+
+/// The rest of the first line of `header` containing `label`, after it.
+fn header_field<'a>(header: &'a str, label: &str) -> Option<&'a str> {
+    header
+        .lines()
+        .find_map(|line| Some(line[line.find(label)? + label.len()..].trim()))
+}
+
+/// `Mars` from `Mars (499)  {source: mar097}`.
+fn body_name(field: &str) -> &str {
+    field.split(['(', '{']).next().unwrap_or(field).trim()
+}
+
+/// From a header's physical data: either its mass, given like `Mass x10^23 (kg) = 6.4171`, or
+/// its standard gravitational parameter, like `GM, km^3/s^2 = 398600.435436`.
+fn mass_kg(header: &str) -> Option<f64> {
+    let mass = physical_data(header, |label| {
+        label.contains("Mass") && label.contains("10^")
+    })
+    .and_then(|(label, mantissa)| {
+        let exponent = label.split("10^").nth(1)?;
+        let digits = exponent
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(exponent.len());
+        Some(mantissa * 10_f64.powi(exponent[..digits].parse().ok()?))
+    });
+    mass.or_else(|| {
+        physical_data(header, |label| {
+            label.contains("GM") && label.contains("km^3/s^2")
+        })
+        .map(|(_, gm)| gm * 1e9 / G)
+    })
+}
+
+/// The label and value of the first of the `label = value` pairs that Horizons lays out several
+/// to a line whose label `is_label` accepts. A label may be run together with the value before
+/// it, but only its own words matter to `is_label`.
+fn physical_data(header: &str, is_label: impl Fn(&str) -> bool) -> Option<(&str, f64)> {
+    header.lines().find_map(|line| {
+        let parts: Vec<_> = line.split('=').collect();
+        parts.windows(2).find_map(|pair| {
+            let value = leading_number(pair[1]).filter(|_| is_label(pair[0]))?;
+            Some((pair[0], value))
+        })
+    })
+}
+
+/// The number at the start of `text`, ignoring a `~` for "about" and anything after it like a
+/// `+-` uncertainty.
+fn leading_number(text: &str) -> Option<f64> {
+    let text = text.trim_start().trim_start_matches('~');
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
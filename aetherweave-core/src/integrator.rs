@@ -0,0 +1,142 @@
+use crate::{
+    simulation::{Body, Gravity},
+    vector::DVec2,
+};
+
+/// Numerical method used to advance the bodies through time.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Integrator {
+    /// First order and cheap, but steadily gains energy so orbits spiral outward.
+    Euler,
+    /// Euler with the velocity updated first and the new velocity used to move: still first
+    /// order and cheap, but symplectic, so energy oscillates instead of climbing.
+    SemiImplicitEuler,
+    /// Second order and symplectic: energy stays within a bounded band over long runs.
+    #[default]
+    Verlet,
+    /// Drift-kick-drift leapfrog: a half step of motion, a full kick from the forces at the
+    /// midpoint, then the other half step. Second order and symplectic like Verlet, with one
+    /// force evaluation per step.
+    Leapfrog,
+    /// Fourth-order Runge-Kutta: very accurate over short runs, but slowly drifts over long ones.
+    Rk4,
+}
+
+impl Integrator {
+    pub const ALL: [Self; 5] = [
+        Self::Euler,
+        Self::SemiImplicitEuler,
+        Self::Verlet,
+        Self::Leapfrog,
+        Self::Rk4,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Euler => "Euler",
+            Self::SemiImplicitEuler => "Semi-implicit Euler",
+            Self::Verlet => "Velocity Verlet",
+            Self::Leapfrog => "Leapfrog",
+            Self::Rk4 => "Runge-Kutta 4",
+        }
+    }
+
+    /// Advances `bodies` by `dt` seconds under their mutual `gravity`.
+    pub fn step(self, bodies: &mut [Body], dt: f64, gravity: Gravity) {
+        match self {
+            Self::Euler => euler(bodies, dt, gravity),
+            Self::SemiImplicitEuler => semi_implicit_euler(bodies, dt, gravity),
+            Self::Verlet => verlet(bodies, dt, gravity),
+            Self::Leapfrog => leapfrog(bodies, dt, gravity),
+            Self::Rk4 => rk4(bodies, dt, gravity),
+        }
+    }
+}
+
+fn positions(bodies: &[Body]) -> Vec<DVec2> {
+    bodies.iter().map(|body| body.position).collect()
+}
+
+fn velocities(bodies: &[Body]) -> Vec<DVec2> {
+    bodies.iter().map(|body| body.velocity).collect()
+}
+
+/// The `gravity` between `bodies` as they are.
+fn accelerations(bodies: &[Body], gravity: Gravity) -> Vec<DVec2> {
+    gravity.accelerations(bodies, &positions(bodies), &velocities(bodies))
+}
+
+fn euler(bodies: &mut [Body], dt: f64, gravity: Gravity) {
+    let accelerations = accelerations(bodies, gravity);
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations) {
+        body.update_position(DVec2::ZERO, dt);
+        body.update_velocity(acceleration, dt);
+    }
+}
+
+fn semi_implicit_euler(bodies: &mut [Body], dt: f64, gravity: Gravity) {
+    let accelerations = accelerations(bodies, gravity);
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations) {
+        body.update_velocity(acceleration, dt);
+        body.update_position(DVec2::ZERO, dt);
+    }
+}
+
+fn verlet(bodies: &mut [Body], dt: f64, gravity: Gravity) {
+    let old_velocities = velocities(bodies);
+    let old_accelerations = gravity.accelerations(bodies, &positions(bodies), &old_velocities);
+    for (body, &acceleration) in bodies.iter_mut().zip(&old_accelerations) {
+        body.update_position(acceleration, dt);
+    }
+    // The new velocities aren't known until the new accelerations are, so any velocity-dependent
+    // term is evaluated at a first-order estimate of them.
+    let predicted_velocities: Vec<_> = old_velocities
+        .iter()
+        .zip(&old_accelerations)
+        .map(|(&v, &a)| v + a * dt)
+        .collect();
+    let new_accelerations =
+        gravity.accelerations(bodies, &positions(bodies), &predicted_velocities);
+    for ((body, old), new) in bodies
+        .iter_mut()
+        .zip(old_accelerations)
+        .zip(new_accelerations)
+    {
+        body.update_velocity((old + new) * 0.5, dt);
+    }
+}
+
+fn leapfrog(bodies: &mut [Body], dt: f64, gravity: Gravity) {
+    for body in bodies.iter_mut() {
+        body.update_position(DVec2::ZERO, dt / 2.);
+    }
+    // Any velocity-dependent term sees the velocities from before the kick.
+    let accelerations = accelerations(bodies, gravity);
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations) {
+        body.update_velocity(acceleration, dt);
+        body.update_position(DVec2::ZERO, dt / 2.);
+    }
+}
+
+fn rk4(bodies: &mut [Body], dt: f64, gravity: Gravity) {
+    let x0 = positions(bodies);
+    let v0 = velocities(bodies);
+    // The velocities and accelerations at the trial state `x0 + dx * h`, `v0 + dv * h`.
+    let derivative = |dx: &[DVec2], dv: &[DVec2], h: f64| {
+        let x: Vec<_> = x0.iter().zip(dx).map(|(&x, &dx)| x + dx * h).collect();
+        let v: Vec<_> = v0.iter().zip(dv).map(|(&v, &dv)| v + dv * h).collect();
+        let a = gravity.accelerations(bodies, &x, &v);
+        (v, a)
+    };
+    let zero = vec![DVec2::ZERO; bodies.len()];
+    let (k1x, k1v) = derivative(&zero, &zero, 0.);
+    let (k2x, k2v) = derivative(&k1x, &k1v, dt / 2.);
+    let (k3x, k3v) = derivative(&k2x, &k2v, dt / 2.);
+    let (k4x, k4v) = derivative(&k3x, &k3v, dt);
+    for (i, body) in bodies.iter_mut().enumerate() {
+        let dx = (k1x[i] + k2x[i] * 2. + k3x[i] * 2. + k4x[i]) * (dt / 6.);
+        let dv = (k1v[i] + k2v[i] * 2. + k3v[i] * 2. + k4v[i]) * (dt / 6.);
+        body.position = x0[i] + dx;
+        body.velocity = v0[i] + dv;
+    }
+}
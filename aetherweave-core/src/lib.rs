@@ -0,0 +1,45 @@
+//! The physics behind Aetherweave, without any GUI, so that it can be driven from tests,
+//! benchmarks or other programs too.
+
+#![warn(clippy::all, rust_2018_idioms)]
+
+pub mod approach;
+pub mod calendar;
+pub mod cr3bp;
+pub mod ephemeris;
+pub mod events;
+pub mod frame;
+pub mod history;
+pub mod horizons;
+pub mod integrator;
+pub mod quadtree;
+pub mod scenario;
+pub mod simulation;
+pub mod spacecraft;
+pub mod system_file;
+pub mod tle;
+pub mod trajectory;
+pub mod vector;
+pub mod world;
+pub use approach::{Approach, ApproachOutcome, ApproachSearch};
+pub use cr3bp::Cr3bp;
+pub use events::{Alignment, AlignmentEvent, AlignmentKind, AlignmentWatcher};
+pub use frame::{Frame, FrameHistory, FrameTransform};
+pub use history::History;
+pub use horizons::{
+    horizons_epoch, horizons_targets, load_horizons, HorizonsError, HorizonsTarget,
+};
+pub use integrator::Integrator;
+pub use quadtree::QuadTree;
+pub use scenario::{generate_belt, scenario, solar_system_on, Scenario};
+pub use simulation::{
+    accelerations, corotating_velocity, lagrange_points, relativistic_accelerations, roche_limit,
+    Body, BodyId, Collisions, Energy, Gravity, Merger, OrbitalElements, Simulation, C,
+    EARTH_MASS_KG, G, SUN_MASS_KG,
+};
+pub use spacecraft::{Maneuver, Spacecraft};
+pub use system_file::{load_system, save_system, SystemFileError};
+pub use tle::{parse_tles, Propagation, Tle, TleError};
+pub use trajectory::Trajectory;
+pub use vector::{dvec2, DVec2};
+pub use world::World;
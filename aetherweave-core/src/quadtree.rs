@@ -0,0 +1,193 @@
+use crate::{
+    simulation::{G, MIN_GRAVITY_DISTANCE},
+    vector::{dvec2, DVec2},
+};
+
+/// A Barnes–Hut tree of point masses, for approximating their gravity in `O(n log n)` instead of
+/// summing over every pair. Each node covers a square and knows the total mass and center of
+/// mass of what's inside it, so a cluster far enough away can stand in for all its members.
+pub struct QuadTree {
+    /// The root first; the four children of a node are stored next to each other.
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    center: DVec2,
+    half_size: f64,
+    mass_kg: f64,
+    /// Kept as a running mean rather than a sum of mass times position, so that a leaf's is
+    /// exactly the position of its occupant and a body can recognize itself.
+    center_of_mass: DVec2,
+    /// Index of the first of four children, or `None` for a leaf.
+    children: Option<usize>,
+}
+
+impl Node {
+    fn new(center: DVec2, half_size: f64) -> Self {
+        Self {
+            center,
+            half_size,
+            mass_kg: 0.,
+            center_of_mass: DVec2::ZERO,
+            children: None,
+        }
+    }
+
+    fn add(&mut self, position: DVec2, mass_kg: f64) {
+        self.mass_kg += mass_kg;
+        self.center_of_mass += (position - self.center_of_mass) * (mass_kg / self.mass_kg);
+    }
+
+    fn contains(&self, position: DVec2) -> bool {
+        (position.x - self.center.x).abs() <= self.half_size
+            && (position.y - self.center.y).abs() <= self.half_size
+    }
+
+    /// Which of the four children `position` falls in.
+    fn quadrant(&self, position: DVec2) -> usize {
+        (position.x >= self.center.x) as usize + 2 * (position.y >= self.center.y) as usize
+    }
+}
+
+impl QuadTree {
+    /// Leaves this deep hold everything that lands in them, so that bodies at the same position
+    /// don't subdivide forever.
+    const MAX_DEPTH: usize = 48;
+
+    /// An empty tree covering the square of side `2 * half_size` around `center`. Masses inserted
+    /// outside it still count, but their positions are approximated more coarsely.
+    pub fn new(center: DVec2, half_size: f64) -> Self {
+        Self {
+            nodes: vec![Node::new(center, half_size)],
+        }
+    }
+
+    /// A tree of the given point masses, covering them all.
+    pub fn from_masses(positions: &[DVec2], masses_kg: &[f64]) -> Self {
+        let (min, max) = positions.iter().fold(
+            (
+                dvec2(f64::INFINITY, f64::INFINITY),
+                -dvec2(f64::INFINITY, f64::INFINITY),
+            ),
+            |(min, max), &p| {
+                (
+                    dvec2(min.x.min(p.x), min.y.min(p.y)),
+                    dvec2(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        );
+        let mut tree = if positions.is_empty() {
+            Self::new(DVec2::ZERO, 1.)
+        } else {
+            let half_size = ((max.x - min.x).max(max.y - min.y) / 2.).max(1.);
+            Self::new((min + max) / 2., half_size)
+        };
+        for (&position, &mass_kg) in positions.iter().zip(masses_kg) {
+            tree.insert(position, mass_kg);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, position: DVec2, mass_kg: f64) {
+        let mut index = 0;
+        for depth in 0.. {
+            let node = &mut self.nodes[index];
+            let was_empty = node.mass_kg == 0.;
+            // A leaf's center of mass is the position of its only occupant, until it splits.
+            let occupant = (node.center_of_mass, node.mass_kg);
+            node.add(position, mass_kg);
+            if let Some(children) = node.children {
+                index = children + node.quadrant(position);
+                continue;
+            }
+            if was_empty || depth >= Self::MAX_DEPTH {
+                return;
+            }
+            // Split the leaf, moving its occupant down into a child, then carry on down with the
+            // new mass.
+            let (occupant_position, occupant_mass_kg) = occupant;
+            let [occupant_quadrant, quadrant] =
+                [occupant_position, position].map(|position| node.quadrant(position));
+            let children = self.split(index);
+            self.nodes[children + occupant_quadrant].add(occupant_position, occupant_mass_kg);
+            index = children + quadrant;
+        }
+    }
+
+    /// Gives the leaf at `index` four empty children, returning the index of the first.
+    fn split(&mut self, index: usize) -> usize {
+        let Node {
+            center, half_size, ..
+        } = self.nodes[index];
+        let quarter = half_size / 2.;
+        let children = self.nodes.len();
+        for quadrant in 0..4 {
+            let x = if quadrant & 1 == 1 { quarter } else { -quarter };
+            let y = if quadrant & 2 == 2 { quarter } else { -quarter };
+            self.nodes.push(Node::new(center + dvec2(x, y), quarter));
+        }
+        self.nodes[index].children = Some(children);
+        children
+    }
+
+    /// The gravitational acceleration at `position` due to everything in the tree, softened as
+    /// in [`crate::accelerations`]. A node whose size is less than `opening_angle` times its
+    /// distance is treated as a single mass at its center of mass; zero makes the sum exact.
+    /// Masses exactly at `position` are left out, so this is the acceleration of a body there
+    /// due to all the others.
+    pub fn acceleration_on(&self, position: DVec2, opening_angle: f64, softening_m: f64) -> DVec2 {
+        let softening_sq = softening_m * softening_m;
+        let mut acceleration = DVec2::ZERO;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.mass_kg == 0. {
+                continue;
+            }
+            let offset = node.center_of_mass - position;
+            let distance = offset.length();
+            let far = !node.contains(position) && 2. * node.half_size < opening_angle * distance;
+            match node.children {
+                Some(children) if !far => stack.extend(children..children + 4),
+                _ if distance == 0. => {}
+                _ => {
+                    let softened = (distance * distance + softening_sq).sqrt();
+                    acceleration += offset / softened * G * node.mass_kg
+                        / (distance.max(MIN_GRAVITY_DISTANCE).powi(2) + softening_sq);
+                }
+            }
+        }
+        acceleration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scenario::generate_belt, simulation::accelerations};
+
+    #[test]
+    fn small_opening_angle_matches_the_exact_sum() {
+        // Without the Sun, whose pull would swamp the asteroids' errors.
+        let bodies = generate_belt(1, 1_000, 3.14e8, 4.94e8);
+        let positions: Vec<DVec2> = bodies.iter().map(|body| body.position).collect();
+        let masses: Vec<f64> = bodies.iter().map(|body| body.mass_kg).collect();
+        let tree = QuadTree::from_masses(&positions, &masses);
+        for (position, exact) in positions.iter().zip(accelerations(&bodies, &positions, 0.)) {
+            let error = |opening_angle| {
+                let approximate = tree.acceleration_on(*position, opening_angle, 0.);
+                (approximate - exact).length() / exact.length()
+            };
+            assert!(
+                error(0.) < 1e-9,
+                "off by {} with no approximation",
+                error(0.)
+            );
+            assert!(
+                error(0.1) < 1e-2,
+                "off by {} of the exact acceleration",
+                error(0.1)
+            );
+        }
+    }
+}
@@ -0,0 +1,508 @@
+use crate::{
+    ephemeris::{elements_on, moon_longitude_deg},
+    simulation::{remove_net_momentum, Body, OrbitalElements, EARTH_MASS_KG, G, SUN_MASS_KG},
+    vector::{dvec2, DVec2},
+};
+use ecolor::Color32;
+
+/// A built-in starting arrangement of bodies.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scenario {
+    #[default]
+    SolarSystem,
+    /// Three equal masses chasing each other around a figure eight (Chenciner & Montgomery).
+    FigureEight,
+    /// Two stars in a close orbit, with a planet circling both.
+    BinaryStar,
+    EarthMoon,
+    /// The Sun, Jupiter and a ring of small bodies between Mars and Jupiter.
+    AsteroidBelt,
+}
+
+impl Scenario {
+    pub const ALL: [Self; 5] = [
+        Self::SolarSystem,
+        Self::FigureEight,
+        Self::BinaryStar,
+        Self::EarthMoon,
+        Self::AsteroidBelt,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SolarSystem => "Solar system",
+            Self::FigureEight => "Figure-eight three-body",
+            Self::BinaryStar => "Binary star",
+            Self::EarthMoon => "Earth and Moon",
+            Self::AsteroidBelt => "Asteroid belt",
+        }
+    }
+}
+
+/// The bodies of `kind`, with no net momentum.
+pub fn scenario(kind: Scenario) -> Vec<Body> {
+    let mut bodies = match kind {
+        Scenario::SolarSystem => solar_system(None),
+        Scenario::FigureEight => figure_eight(),
+        Scenario::BinaryStar => binary_star(),
+        Scenario::EarthMoon => earth_moon(),
+        Scenario::AsteroidBelt => asteroid_belt(),
+    };
+    remove_net_momentum(&mut bodies);
+    bodies
+}
+
+/// The solar system with the planets, Pluto, Comet Encke and the Moon where they are on
+/// `julian_date`, going by [`crate::ephemeris`], and with no net momentum. The other moons keep
+/// their usual places around their planets.
+pub fn solar_system_on(julian_date: f64) -> Vec<Body> {
+    let mut bodies = solar_system(Some(julian_date));
+    remove_net_momentum(&mut bodies);
+    bodies
+}
+
+const AU_M: f64 = 1.495_978_707e11;
+const SUN_RADIUS_KM: f32 = 696_340.;
+
+/// An orbit for [`Body::from_elements`], with the semi-major axis in AU and the angles in degrees.
+/// The period and distance are left for it to work out.
+pub(crate) fn elements(
+    semi_major_axis_au: f64,
+    eccentricity: f64,
+    inclination_deg: f32,
+    argument_of_periapsis_deg: f64,
+    true_anomaly_deg: f64,
+) -> OrbitalElements {
+    OrbitalElements {
+        semi_major_axis_m: semi_major_axis_au * AU_M,
+        eccentricity,
+        inclination_deg,
+        argument_of_periapsis_deg,
+        true_anomaly_deg,
+        period_s: None,
+        distance_m: 0.,
+    }
+}
+
+fn solar_system(julian_date: Option<f64>) -> Vec<Body> {
+    let sun = Body::orbiting("Sun", SUN_MASS_KG, 696_340., 0., Color32::GOLD, 0., 0.);
+    let on_date = |name| julian_date.and_then(|date| elements_on(name, date));
+    // Without a date, each planet has its periapsis along the x axis, `degrees` past it.
+    let planet = |name,
+                  mass_kg,
+                  radius_km,
+                  semi_major_axis_km,
+                  eccentricity,
+                  color,
+                  degrees,
+                  inclination_deg| {
+        match on_date(name) {
+            Some(elements) => Body::from_elements(&sun, name, mass_kg, radius_km, color, &elements),
+            None => Body::orbiting_elliptical(
+                name,
+                mass_kg,
+                radius_km,
+                semi_major_axis_km,
+                eccentricity,
+                color,
+                degrees,
+            )
+            .inclined(inclination_deg),
+        }
+    };
+    let earth = planet(
+        "Earth",
+        EARTH_MASS_KG,
+        6_371.,
+        1.5e8,
+        0.0167,
+        Color32::BLUE,
+        40.,
+        0.,
+    );
+    let jupiter = planet(
+        "Jupiter",
+        1.899e27,
+        69_911.,
+        778.5e6,
+        0.0489,
+        Color32::BROWN,
+        75.,
+        1.3,
+    );
+    let saturn = planet(
+        "Saturn",
+        5.683e26,
+        58_232.,
+        1.434e9,
+        0.0565,
+        Color32::YELLOW,
+        60.,
+        2.49,
+    );
+    // Periapses are at their longitudes of perihelion, flattened onto the ecliptic.
+    let pluto = Body::from_elements(
+        &sun,
+        "Pluto",
+        1.303e22,
+        1_188.3,
+        Color32::LIGHT_RED,
+        &on_date("Pluto").unwrap_or_else(|| elements(39.48, 0.2488, 17.16, 224.07, 40.)),
+    );
+    let encke = Body::from_elements(
+        &sun,
+        "Comet Encke",
+        1e13,
+        2.4,
+        Color32::LIGHT_GREEN,
+        &on_date("Comet Encke").unwrap_or_else(|| elements(2.215, 0.8483, 11.78, 161.12, 180.)),
+    )
+    .gravitating(false);
+    let moon_degrees = julian_date.map_or(0., |date| moon_longitude_deg(date) as f32);
+    vec![
+        sun.clone(),
+        planet(
+            "Mercury",
+            3.285e23,
+            2_439.7,
+            57.9e6,
+            0.2056,
+            Color32::GRAY,
+            200.,
+            7.0,
+        ),
+        planet(
+            "Venus",
+            4.867e24,
+            6_051.8,
+            108.2e6,
+            0.0068,
+            Color32::GREEN,
+            110.,
+            3.39,
+        ),
+        earth.clone(),
+        planet(
+            "Mars",
+            6.39e23,
+            3_389.5,
+            228e6,
+            0.0934,
+            Color32::RED,
+            40.,
+            1.85,
+        ),
+        jupiter.clone(),
+        saturn.clone(),
+        planet(
+            "Uranus",
+            8.681e25,
+            25_362.,
+            2.871e9,
+            0.0457,
+            Color32::LIGHT_BLUE,
+            30.,
+            0.77,
+        ),
+        planet(
+            "Neptune",
+            1.024e26,
+            24_622.,
+            4.495e9,
+            0.0113,
+            Color32::BLUE,
+            15.,
+            1.77,
+        ),
+        pluto,
+        encke,
+        Body::orbiting_around(
+            &earth,
+            "Luna",
+            7.342e22,
+            1_737.4,
+            384_400.,
+            Color32::LIGHT_GRAY,
+            moon_degrees,
+        ),
+        Body::orbiting_around(
+            &jupiter,
+            "Io",
+            8.932e22,
+            1_821.6,
+            421_700.,
+            Color32::KHAKI,
+            0.,
+        ),
+        Body::orbiting_around(
+            &jupiter,
+            "Europa",
+            4.800e22,
+            1_560.8,
+            671_034.,
+            Color32::WHITE,
+            90.,
+        ),
+        Body::orbiting_around(
+            &jupiter,
+            "Ganymede",
+            1.482e23,
+            2_634.1,
+            1_070_412.,
+            Color32::GRAY,
+            180.,
+        ),
+        Body::orbiting_around(
+            &jupiter,
+            "Callisto",
+            1.076e23,
+            2_410.3,
+            1_882_709.,
+            Color32::DARK_GRAY,
+            270.,
+        ),
+        Body::orbiting_around(
+            &saturn,
+            "Titan",
+            1.345e23,
+            2_574.7,
+            1_221_870.,
+            Color32::GOLD,
+            0.,
+        ),
+    ]
+}
+
+fn figure_eight() -> Vec<Body> {
+    // The published initial conditions are in units where `G = m = 1`; here one unit of length
+    // is an AU and each mass is the Sun's, which makes the period about a year.
+    let position = dvec2(0.970_004_36, -0.243_087_53);
+    let velocity = dvec2(-0.932_407_37, -0.864_731_46);
+    let speed_unit = (G * SUN_MASS_KG / AU_M).sqrt();
+    let star = |name, position: DVec2, velocity: DVec2, color| {
+        Body::new(
+            name,
+            SUN_MASS_KG,
+            SUN_RADIUS_KM,
+            position * AU_M,
+            velocity * speed_unit,
+            color,
+        )
+    };
+    vec![
+        star("A", position, velocity * -0.5, Color32::GOLD),
+        star("B", -position, velocity * -0.5, Color32::LIGHT_BLUE),
+        star("C", DVec2::ZERO, velocity, Color32::LIGHT_RED),
+    ]
+}
+
+fn binary_star() -> Vec<Body> {
+    let (m1, m2) = (SUN_MASS_KG, 0.8 * SUN_MASS_KG);
+    let total = m1 + m2;
+    let separation = 0.2 * AU_M;
+    let speed = (G * total / separation).sqrt();
+    // Each star sits opposite the other across the barycenter at the origin.
+    let (r1, r2) = (separation * m2 / total, separation * m1 / total);
+    let (v1, v2) = (speed * m2 / total, speed * m1 / total);
+    let planet_distance = 2. * AU_M;
+    vec![
+        Body::new(
+            "Primary",
+            m1,
+            SUN_RADIUS_KM,
+            dvec2(r1, 0.),
+            dvec2(0., v1),
+            Color32::GOLD,
+        ),
+        Body::new(
+            "Secondary",
+            m2,
+            0.8 * SUN_RADIUS_KM,
+            dvec2(-r2, 0.),
+            dvec2(0., -v2),
+            Color32::LIGHT_RED,
+        ),
+        Body::new(
+            "Planet",
+            EARTH_MASS_KG,
+            6_371.,
+            dvec2(0., planet_distance),
+            dvec2(-(G * total / planet_distance).sqrt(), 0.),
+            Color32::LIGHT_BLUE,
+        ),
+    ]
+}
+
+fn earth_moon() -> Vec<Body> {
+    let earth = Body::new(
+        "Earth",
+        EARTH_MASS_KG,
+        6_371.,
+        DVec2::ZERO,
+        DVec2::ZERO,
+        Color32::BLUE,
+    );
+    let moon = Body::orbiting_around(
+        &earth,
+        "Luna",
+        7.342e22,
+        1_737.4,
+        384_400.,
+        Color32::LIGHT_GRAY,
+        0.,
+    );
+    vec![earth, moon]
+}
+
+fn asteroid_belt() -> Vec<Body> {
+    let mut bodies = vec![
+        Body::orbiting("Sun", SUN_MASS_KG, SUN_RADIUS_KM, 0., Color32::GOLD, 0., 0.),
+        Body::orbiting_elliptical(
+            "Jupiter",
+            1.899e27,
+            69_911.,
+            778.5e6,
+            0.0489,
+            Color32::BROWN,
+            75.,
+        ),
+    ];
+    bodies.extend(generate_belt(
+        DEFAULT_BELT_SEED,
+        200,
+        BELT_INNER_KM,
+        BELT_OUTER_KM,
+    ));
+    bodies
+}
+
+pub const DEFAULT_BELT_SEED: u64 = 1;
+/// Edges of the main asteroid belt, about 2.1 and 3.3 AU from the Sun.
+pub const BELT_INNER_KM: f32 = 3.14e8;
+pub const BELT_OUTER_KM: f32 = 4.94e8;
+
+/// `count` small bodies on near-circular orbits around the Sun at the origin, spread between
+/// `inner_km` and `outer_km` from it.
+///
+/// The same `seed` gives bit-for-bit the same belt on every platform: the only floating-point
+/// operations used are the correctly rounded `+ - * /` and `sqrt`, never `sin` or `cos`.
+pub fn generate_belt(seed: u64, count: usize, inner_km: f32, outer_km: f32) -> Vec<Body> {
+    let mut random = SplitMix64(seed);
+    let (inner, outer) = (inner_km as f64 * 1e3, outer_km as f64 * 1e3);
+    (0..count)
+        .map(|i| {
+            // A uniformly random direction, from a point picked uniformly inside the unit disc.
+            let direction = loop {
+                let point = dvec2(random.next_f64() * 2. - 1., random.next_f64() * 2. - 1.);
+                let length = point.length_sq().sqrt();
+                if length > 1e-3 && length <= 1. {
+                    break point / length;
+                }
+            };
+            let distance = inner + (outer - inner) * random.next_f64();
+            // Nudging the circular speed gives each orbit a slight eccentricity, with its
+            // periapsis or apoapsis where the body starts.
+            let speed =
+                (G * SUN_MASS_KG / distance).sqrt() * (1. + 0.05 * (random.next_f64() - 0.5));
+            Body::new(
+                &format!("Asteroid {}", i + 1),
+                1e18,
+                50.,
+                direction * distance,
+                dvec2(-direction.y, direction.x) * speed,
+                Color32::GRAY,
+            )
+        })
+        .collect()
+}
+
+/// Steele, Lea and Flood's SplitMix64: tiny, fast, and defined purely by integer arithmetic.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`, from the top 53 bits.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{integrator::Integrator, simulation::Simulation};
+
+    #[test]
+    fn belt_is_the_same_to_the_bit_for_a_seed() {
+        // The position's x and y, then the velocity's, as generated on x86-64 Linux. Any platform
+        // that gives different bits breaks saved systems and shared seeds.
+        const EXPECTED: [[u64; 4]; 3] = [
+            [
+                0x4248_8c7a_c09b_1689,
+                0xc251_47d5_abfe_bbd8,
+                0x40ce_2a78_c331_2578,
+                0x40c5_6d41_1373_9bad,
+            ],
+            [
+                0xc24d_dbe2_408d_c0a6,
+                0x424f_e0a5_0399_618e,
+                0xc0ca_f8ea_0ed8_97e0,
+                0xc0c9_43ab_b79a_fbac,
+            ],
+            [
+                0xc257_a64a_bdde_c416,
+                0xc201_faba_4ada_0909,
+                0x407a_dafe_50c6_aa29,
+                0xc0d1_a994_e0cd_3c8b,
+            ],
+        ];
+        let belt = generate_belt(42, 3, BELT_INNER_KM, BELT_OUTER_KM);
+        let found: Vec<_> = belt
+            .iter()
+            .map(|body| {
+                [
+                    body.position.x,
+                    body.position.y,
+                    body.velocity.x,
+                    body.velocity.y,
+                ]
+                .map(f64::to_bits)
+            })
+            .collect();
+        assert_eq!(found, EXPECTED);
+    }
+
+    #[test]
+    fn figure_eight_stays_bounded_and_comes_back_around() {
+        const PERIODS: f64 = 3.;
+        // 6.3259 in the published units of time, each an AU over the unit of speed.
+        let period_s = 6.325_9 * (AU_M.powi(3) / (G * SUN_MASS_KG)).sqrt();
+        let mut simulation = Simulation::new(figure_eight());
+        let positions = |simulation: &Simulation| -> Vec<DVec2> {
+            simulation
+                .bodies()
+                .iter()
+                .map(|body| body.position)
+                .collect()
+        };
+        let start = positions(&simulation);
+        while simulation.elapsed_seconds() < PERIODS * period_s {
+            simulation.step(Simulation::DEFAULT_TIMESTEP, Integrator::Verlet);
+            assert_eq!(simulation.bodies().len(), 3);
+            assert!(positions(&simulation)
+                .iter()
+                .all(|position| position.length() < 1.5 * AU_M));
+        }
+        for (position, start) in positions(&simulation).into_iter().zip(start) {
+            let off = (position - start).length() / AU_M;
+            assert!(off < 0.05, "a star ended {off} AU from where it started");
+        }
+    }
+}
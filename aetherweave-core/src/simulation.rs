@@ -0,0 +1,1430 @@
+use crate::{
+    integrator::Integrator,
+    quadtree::QuadTree,
+    scenario::{scenario, Scenario},
+    spacecraft::{maneuver_axes, Spacecraft},
+    trajectory::Trajectory,
+    vector::{dvec2, DVec2},
+    world::World,
+};
+use ecolor::Color32;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Newtonian constant of gravitation, in m³ kg⁻¹ s⁻².
+pub const G: f64 = 6.674e-11;
+/// Speed of light, in m/s.
+pub const C: f64 = 299_792_458.;
+pub const SUN_MASS_KG: f64 = 1.9891e30;
+pub const EARTH_MASS_KG: f64 = 5.97219e24;
+/// Gravity is computed as if bodies were never closer than this, in meters, so that a close
+/// pass doesn't blow up the `1/r²` term.
+pub(crate) const MIN_GRAVITY_DISTANCE: f64 = 1e6;
+/// From this many bodies, [`accelerations`] is spread over every core, where there are threads to
+/// spread it over. With fewer, starting the threads costs more than they save.
+#[cfg(not(target_arch = "wasm32"))]
+const PARALLEL_MIN_BODIES: usize = 256;
+
+/// Names a body for as long as it exists: unlike its index, it doesn't change as other bodies
+/// come and go, and it is kept by [`Simulation::duplicate`] and through saving and loading.
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct BodyId(u64);
+
+static NEXT_BODY_ID: AtomicU64 = AtomicU64::new(0);
+
+impl BodyId {
+    fn next() -> Self {
+        Self(NEXT_BODY_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for BodyId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+        // Bodies made from now on mustn't be given the ids of those loaded.
+        NEXT_BODY_ID.fetch_max(id.saturating_add(1), Ordering::Relaxed);
+        Ok(Self(id))
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct Body {
+    #[serde(default = "BodyId::next")]
+    pub id: BodyId,
+    pub name: String,
+    pub mass_kg: f64,
+    pub radius_km: f32,
+    /// In meters.
+    pub position: DVec2,
+    pub color: Color32,
+    /// In m/s.
+    pub velocity: DVec2,
+    /// Tilt of the body's orbital plane about the x axis, in degrees. This only changes how the
+    /// body is drawn: the physics stays in the plane.
+    #[serde(default)]
+    pub inclination_deg: f32,
+    /// Whether the body pulls on others. One that doesn't is a massless test particle: it still
+    /// falls in everyone else's gravity, but leaves them alone, and costs little to simulate.
+    #[serde(default = "gravitates_by_default")]
+    pub gravitates: bool,
+    /// The propellant, engine and planned burns of a body that is a spacecraft. Its mass is kept
+    /// at the spacecraft's.
+    #[serde(default)]
+    pub spacecraft: Option<Spacecraft>,
+    /// Recent positions, oldest first, each with the simulated time it was reached at in seconds.
+    #[serde(skip)]
+    pub trail: VecDeque<(f64, DVec2)>,
+    /// Whether the trail was laid while time ran backwards.
+    #[serde(skip)]
+    trail_backwards: bool,
+    /// Where the body was before the last step, if it has taken one.
+    #[serde(skip)]
+    previous_position: Option<DVec2>,
+}
+
+fn gravitates_by_default() -> bool {
+    true
+}
+
+impl Body {
+    pub fn new(
+        name: &str,
+        mass_kg: f64,
+        radius_km: f32,
+        position: DVec2,
+        velocity: DVec2,
+        color: Color32,
+    ) -> Self {
+        Self {
+            id: BodyId::next(),
+            name: name.to_string(),
+            mass_kg,
+            radius_km,
+            position,
+            color,
+            velocity,
+            inclination_deg: 0.,
+            gravitates: true,
+            spacecraft: None,
+            trail: VecDeque::new(),
+            trail_backwards: false,
+            previous_position: None,
+        }
+    }
+
+    /// Gives a body that is still being built the id of another.
+    fn identified(self, id: BodyId) -> Self {
+        Self { id, ..self }
+    }
+
+    /// Makes the body a test particle if `gravitates` is false, for building bodies in place.
+    pub fn gravitating(self, gravitates: bool) -> Self {
+        Self { gravitates, ..self }
+    }
+
+    /// Makes the body a spacecraft, or not if `spacecraft` is `None`, for building bodies in place.
+    pub fn piloted(self, spacecraft: Option<Spacecraft>) -> Self {
+        Self { spacecraft, ..self }
+    }
+
+    /// A body on a circular orbit around the Sun at the origin, its plane tilted by
+    /// `inclination_deg`.
+    pub fn orbiting(
+        name: &str,
+        mass_kg: f64,
+        radius_km: f32,
+        orbital_radius_km: f32,
+        color: Color32,
+        degrees: f32,
+        inclination_deg: f32,
+    ) -> Self {
+        Self::orbiting_elliptical(
+            name,
+            mass_kg,
+            radius_km,
+            orbital_radius_km,
+            0.,
+            color,
+            degrees,
+        )
+        .inclined(inclination_deg)
+    }
+
+    /// Sets the tilt of the body's orbital plane, for building bodies in place.
+    pub fn inclined(self, inclination_deg: f32) -> Self {
+        Self {
+            inclination_deg,
+            ..self
+        }
+    }
+
+    /// A body on a Keplerian ellipse around the Sun at the origin, with its periapsis along the
+    /// positive x axis, `true_anomaly_degrees` past periapsis.
+    pub fn orbiting_elliptical(
+        name: &str,
+        mass_kg: f64,
+        radius_km: f32,
+        semi_major_axis_km: f32,
+        eccentricity: f32,
+        color: Color32,
+        true_anomaly_degrees: f32,
+    ) -> Self {
+        let (position, velocity) = kepler_state(
+            G * SUN_MASS_KG,
+            semi_major_axis_km as f64 * 1e3,
+            eccentricity as f64,
+            true_anomaly_degrees as f64,
+        );
+        Self::new(name, mass_kg, radius_km, position, velocity, color)
+    }
+
+    /// A body on a circular orbit around `parent`, placed relative to the parent's current
+    /// position and moving with it, such as a moon around its planet. It is drawn inclined as
+    /// the parent is, to stay beside it.
+    pub fn orbiting_around(
+        parent: &Body,
+        name: &str,
+        mass_kg: f64,
+        radius_km: f32,
+        orbital_radius_km: f32,
+        color: Color32,
+        degrees: f32,
+    ) -> Self {
+        // The pair orbits their common barycenter, so their separation follows a Kepler orbit
+        // in the combined mass.
+        let (offset, relative_velocity) = kepler_state(
+            G * (parent.mass_kg + mass_kg),
+            orbital_radius_km as f64 * 1e3,
+            0.,
+            degrees as f64,
+        );
+        Self::new(
+            name,
+            mass_kg,
+            radius_km,
+            parent.position + offset,
+            parent.velocity + relative_velocity,
+            color,
+        )
+        .inclined(parent.inclination_deg)
+    }
+
+    /// Whether the two bodies' surfaces touch.
+    pub fn overlaps(&self, other: &Body) -> bool {
+        let reach = (self.radius_km + other.radius_km) as f64 * 1e3;
+        (self.position - other.position).length_sq() < reach * reach
+    }
+
+    /// One body with the pair's combined mass, volume and momentum, at their center of mass. It
+    /// keeps the id, name and trail of the heavier of the two, takes on a mix of their colors
+    /// weighted by mass, and gravitates if either did.
+    pub fn merge(a: &Body, b: &Body) -> Self {
+        let (heavier, lighter) = if a.mass_kg >= b.mass_kg {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let (m1, m2) = (heavier.mass_kg, lighter.mass_kg);
+        let mass_kg = m1 + m2;
+        let weighted = |x1: DVec2, x2: DVec2| (x1 * m1 + x2 * m2) / mass_kg;
+        let mut merged = Self::new(
+            &heavier.name,
+            mass_kg,
+            (heavier.radius_km.powi(3) + lighter.radius_km.powi(3)).cbrt(),
+            weighted(heavier.position, lighter.position),
+            weighted(heavier.velocity, lighter.velocity),
+            heavier
+                .color
+                .lerp_to_gamma(lighter.color, (m2 / mass_kg) as f32),
+        )
+        .inclined(heavier.inclination_deg)
+        .gravitating(heavier.gravitates || lighter.gravitates)
+        .identified(heavier.id);
+        merged.trail = heavier.trail.clone();
+        merged.trail_backwards = heavier.trail_backwards;
+        merged
+    }
+
+    /// The body's position, moved `lag` of the way back towards where it was before the last
+    /// step; see [`Simulation::step_lag`].
+    pub fn drawn_position(&self, lag: f64) -> DVec2 {
+        let position = self.position;
+        self.previous_position
+            .map_or(position, |previous| position + (previous - position) * lag)
+    }
+
+    pub fn update_velocity(&mut self, acceleration: DVec2, dt: f64) {
+        self.velocity += acceleration * dt;
+    }
+
+    pub fn update_position(&mut self, acceleration: DVec2, dt: f64) {
+        self.position += self.velocity * dt + acceleration * (0.5 * dt * dt);
+    }
+
+    /// Trails are cut short at this many points, however brief the steps between them.
+    const MAX_TRAIL_POINTS: usize = 20_000;
+
+    /// Appends the current position, reached at simulated time `seconds`, to the trail, dropping
+    /// the oldest points `duration_s` or more before it. A step in the other direction in time
+    /// from the one the trail was laid in retraces it, so takes the newest point off instead,
+    /// until the trail runs out and starts again this way.
+    pub fn record_trail(&mut self, seconds: f64, duration_s: f64, backwards: bool) {
+        if backwards != self.trail_backwards {
+            if self.trail.len() > 1 {
+                self.trail.pop_back();
+                return;
+            }
+            self.trail_backwards = backwards;
+        }
+        self.trail.push_back((seconds, self.position));
+        while self.trail.len() > Self::MAX_TRAIL_POINTS
+            || self
+                .trail
+                .front()
+                .is_some_and(|&(time, _)| (seconds - time).abs() >= duration_s)
+        {
+            self.trail.pop_front();
+        }
+    }
+
+    /// The osculating Keplerian orbit of `self` around `central`, as if they were alone.
+    pub fn orbital_elements(&self, central: &Body) -> OrbitalElements {
+        let DVec2 { x, y } = self.position - central.position;
+        let DVec2 { x: vx, y: vy } = self.velocity - central.velocity;
+        let mu = G * (self.mass_kg + central.mass_kg);
+        let distance = x.hypot(y);
+        let energy = (vx * vx + vy * vy) / 2. - mu / distance;
+        let angular_momentum = x * vy - y * vx;
+        let semi_major_axis = -mu / (2. * energy);
+        let eccentricity = (1. + 2. * energy * angular_momentum.powi(2) / mu.powi(2))
+            .max(0.)
+            .sqrt();
+        let period = (semi_major_axis > 0.)
+            .then(|| std::f64::consts::TAU * (semi_major_axis.powi(3) / mu).sqrt());
+        // The eccentricity vector points at periapsis. A circular orbit has none, so its angles
+        // are measured from the x axis instead.
+        let speed_sq = vx * vx + vy * vy;
+        let radial = x * vx + y * vy;
+        let eccentricity_vector = dvec2(
+            (speed_sq - mu / distance) * x - radial * vx,
+            (speed_sq - mu / distance) * y - radial * vy,
+        ) / mu;
+        let argument_of_periapsis = if eccentricity > 1e-9 {
+            eccentricity_vector.y.atan2(eccentricity_vector.x)
+        } else {
+            0.
+        };
+        // In the direction of motion, which is clockwise for a retrograde orbit.
+        let true_anomaly = (y.atan2(x) - argument_of_periapsis) * angular_momentum.signum();
+        OrbitalElements {
+            semi_major_axis_m: semi_major_axis,
+            eccentricity,
+            inclination_deg: self.inclination_deg,
+            argument_of_periapsis_deg: argument_of_periapsis.to_degrees().rem_euclid(360.),
+            true_anomaly_deg: true_anomaly.to_degrees().rem_euclid(360.),
+            period_s: period,
+            distance_m: distance,
+        }
+    }
+
+    /// A body on the orbit described by `elements` around `primary`, moving with it, as
+    /// [`Self::orbital_elements`] would describe it. The orbit runs counter-clockwise, and the
+    /// `period_s` and `distance_m` of `elements` are ignored, following from the rest. A bound
+    /// orbit needs an eccentricity in `[0, 1)`; a hyperbolic one needs a negative semi-major axis
+    /// and a true anomaly short of its asymptotes.
+    pub fn from_elements(
+        primary: &Body,
+        name: &str,
+        mass_kg: f64,
+        radius_km: f32,
+        color: Color32,
+        elements: &OrbitalElements,
+    ) -> Self {
+        let (offset, relative_velocity) = kepler_state(
+            G * (primary.mass_kg + mass_kg),
+            elements.semi_major_axis_m,
+            elements.eccentricity,
+            elements.true_anomaly_deg,
+        );
+        let [offset, relative_velocity] = [offset, relative_velocity]
+            .map(|vector| vector.rotated(elements.argument_of_periapsis_deg.to_radians()));
+        Self::new(
+            name,
+            mass_kg,
+            radius_km,
+            primary.position + offset,
+            primary.velocity + relative_velocity,
+            color,
+        )
+        .inclined(elements.inclination_deg)
+    }
+
+    /// Whether the two-body orbital energy of `self` relative to `other` is negative.
+    pub fn is_bound_to(&self, other: &Body) -> bool {
+        let distance = (self.position - other.position).length();
+        let speed = (self.velocity - other.velocity).length();
+        distance > 0. && speed * speed / 2. < G * (self.mass_kg + other.mass_kg) / distance
+    }
+}
+
+/// Shape and size of an orbit, derived from a body's instantaneous state relative to its
+/// central body.
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitalElements {
+    /// Negative for an unbound (hyperbolic) trajectory.
+    pub semi_major_axis_m: f64,
+    pub eccentricity: f64,
+    /// The tilt the body is drawn with; see [`Body::inclination_deg`].
+    pub inclination_deg: f32,
+    /// Direction of periapsis, counter-clockwise from the positive x axis.
+    pub argument_of_periapsis_deg: f64,
+    /// How far the body is past periapsis, in its direction of motion.
+    pub true_anomaly_deg: f64,
+    /// `None` for an unbound trajectory.
+    pub period_s: Option<f64>,
+    pub distance_m: f64,
+}
+
+impl OrbitalElements {
+    /// Closest distance to the central body.
+    pub fn periapsis_m(&self) -> f64 {
+        self.semi_major_axis_m * (1. - self.eccentricity)
+    }
+
+    /// Farthest distance from the central body, or `None` for an unbound trajectory.
+    pub fn apoapsis_m(&self) -> Option<f64> {
+        self.period_s
+            .map(|_| self.semi_major_axis_m * (1. + self.eccentricity))
+    }
+
+    /// Time until the body next passes periapsis, or `None` for an unbound trajectory.
+    pub fn time_to_periapsis_s(&self) -> Option<f64> {
+        let period_s = self.period_s?;
+        let e = self.eccentricity;
+        let true_anomaly = self.true_anomaly_deg.to_radians();
+        // Kepler's equation, through the eccentric anomaly.
+        let eccentric_anomaly =
+            2. * (((1. - e) / (1. + e)).sqrt() * (true_anomaly / 2.).tan()).atan();
+        let mean_anomaly =
+            (eccentric_anomaly - e * eccentric_anomaly.sin()).rem_euclid(std::f64::consts::TAU);
+        Some(period_s * (1. - mean_anomaly / std::f64::consts::TAU))
+    }
+}
+
+/// Energy of a whole [`Simulation`], in joules.
+pub struct Energy {
+    pub kinetic_j: f64,
+    pub potential_j: f64,
+}
+
+impl Energy {
+    pub fn total_j(&self) -> f64 {
+        self.kinetic_j + self.potential_j
+    }
+}
+
+/// How the gravity between bodies is computed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Gravity {
+    /// Plummer softening length, in meters; see [`accelerations`].
+    pub softening_m: f64,
+    /// Add the [`relativistic_accelerations`].
+    pub relativity: bool,
+    /// Approximate with a [`QuadTree`] with this opening angle, or sum over every pair if `None`.
+    pub opening_angle: Option<f64>,
+}
+
+impl Gravity {
+    /// The acceleration of each of `bodies` were they at `positions` moving at `velocities`,
+    /// which only matter with relativity.
+    pub fn accelerations(
+        self,
+        bodies: &[Body],
+        positions: &[DVec2],
+        velocities: &[DVec2],
+    ) -> Vec<DVec2> {
+        let mut total = match self.opening_angle {
+            Some(opening_angle) => {
+                let (sources, masses): (Vec<_>, Vec<_>) = bodies
+                    .iter()
+                    .zip(positions)
+                    .filter(|(body, _)| body.gravitates)
+                    .map(|(body, &position)| (position, body.mass_kg))
+                    .unzip();
+                let tree = QuadTree::from_masses(&sources, &masses);
+                positions
+                    .iter()
+                    .map(|&position| {
+                        tree.acceleration_on(position, opening_angle, self.softening_m)
+                    })
+                    .collect()
+            }
+            None => accelerations(bodies, positions, self.softening_m),
+        };
+        if self.relativity {
+            for (total, correction) in total
+                .iter_mut()
+                .zip(relativistic_accelerations(bodies, positions, velocities))
+            {
+                *total += correction;
+            }
+        }
+        total
+    }
+}
+
+/// What happens when two bodies' surfaces touch.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Collisions {
+    /// They pass through each other.
+    PassThrough,
+    /// They are replaced by their [`Body::merge`].
+    #[default]
+    Merge,
+    /// They bounce off each other elastically, conserving momentum and kinetic energy.
+    Bounce,
+}
+
+impl Collisions {
+    pub const ALL: [Self; 3] = [Self::PassThrough, Self::Merge, Self::Bounce];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PassThrough => "Pass through",
+            Self::Merge => "Merge",
+            Self::Bounce => "Bounce",
+        }
+    }
+}
+
+/// Two bodies that collided and were replaced by one.
+pub struct Merger {
+    /// The pair as they were when they touched, no longer in the simulation.
+    pub originals: [Body; 2],
+    /// The body that replaced them, which has the heavier one's id.
+    pub merged: BodyId,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Simulation {
+    bodies: World,
+    /// How far back each body's trail reaches, in simulated seconds.
+    pub trail_duration_s: f64,
+    /// What happens to bodies whose surfaces touch.
+    pub collisions: Collisions,
+    /// Plummer softening length, in meters; see [`accelerations`].
+    pub softening_m: f64,
+    /// Whether to add the first post-Newtonian correction; see [`relativistic_accelerations`].
+    pub relativity: bool,
+    /// Approximate gravity with a [`QuadTree`] instead of summing over every pair once there are
+    /// at least [`Self::barnes_hut_min_bodies`], which is much faster with thousands of bodies.
+    pub barnes_hut: bool,
+    pub barnes_hut_min_bodies: usize,
+    /// How large a cluster may look, as its size over its distance, and still be approximated by
+    /// its center of mass; see [`QuadTree::acceleration_on`].
+    pub opening_angle: f64,
+    /// Whether [`Self::advance`] picks each step's length from how close the closest encounter
+    /// is, within [`Self::min_timestep_s`] and [`Self::max_timestep_s`], instead of always
+    /// stepping by [`Self::timestep_s`].
+    pub adaptive_timestep: bool,
+    /// Length of one fixed physics step, in simulated seconds.
+    pub timestep_s: f64,
+    pub min_timestep_s: f64,
+    pub max_timestep_s: f64,
+    /// The body whose state is being sampled each step, if any.
+    #[serde(skip)]
+    pub trajectory: Option<Trajectory>,
+    /// Simulated seconds not yet consumed by a physics step.
+    #[serde(skip)]
+    accumulator: f64,
+    /// Simulated seconds since the start.
+    elapsed_seconds: f64,
+}
+
+/// Position and velocity, relative to the focus, of a body `true_anomaly_degrees` past periapsis
+/// on a Keplerian ellipse with its periapsis along the positive x axis. `mu` is the gravitational
+/// parameter `G (M + m)`.
+fn kepler_state(
+    mu: f64,
+    semi_major_axis: f64,
+    eccentricity: f64,
+    true_anomaly_degrees: f64,
+) -> (DVec2, DVec2) {
+    let anomaly = true_anomaly_degrees.to_radians();
+    let (sin, cos) = anomaly.sin_cos();
+    let semi_latus_rectum = semi_major_axis * (1. - eccentricity * eccentricity);
+    let distance = semi_latus_rectum / (1. + eccentricity * cos);
+    let radial = dvec2(cos, sin);
+    let tangential = dvec2(-sin, cos);
+    let velocity = if semi_latus_rectum > 0. {
+        // Components of the vis-viva speed `sqrt(GM (2/r - 1/a))` along and across the radius.
+        let speed = (mu / semi_latus_rectum).sqrt();
+        (radial * eccentricity * sin + tangential * (1. + eccentricity * cos)) * speed
+    } else {
+        DVec2::ZERO
+    };
+    (radial * distance, velocity)
+}
+
+/// Where L1 through L5 of `secondary`'s orbit around `primary` are, using the approximations for
+/// a secondary much lighter than its primary on a near-circular orbit: L1 and L2 a Hill radius
+/// either side of the secondary, L3 just beyond the opposite side of the primary, and L4 and L5
+/// leading and trailing it by 60°.
+pub fn lagrange_points(primary: &Body, secondary: &Body) -> [DVec2; 5] {
+    let center = primary.position;
+    let offset = secondary.position - center;
+    let [m1, m2] = [primary, secondary].map(|body| body.mass_kg);
+    let mass_ratio = m2 / (m1 + m2);
+    let hill = (mass_ratio / 3.).cbrt();
+    // Counter-clockwise orbits lead toward positive angles; clockwise ones the other way.
+    let relative_velocity = secondary.velocity - primary.velocity;
+    let direction = (offset.x * relative_velocity.y - offset.y * relative_velocity.x).signum();
+    let rotated = |degrees: f64| {
+        let (sin, cos) = (direction * degrees).to_radians().sin_cos();
+        dvec2(
+            offset.x * cos - offset.y * sin,
+            offset.x * sin + offset.y * cos,
+        )
+    };
+    [
+        center + offset * (1. - hill),
+        center + offset * (1. + hill),
+        center - offset * (1. + 5. / 12. * mass_ratio),
+        center + rotated(60.),
+        center + rotated(-60.),
+    ]
+}
+
+/// The velocity that keeps something at `point` turning with `secondary`'s orbit around
+/// `primary`, about their barycenter, as it must to stay at one of their Lagrange points.
+pub fn corotating_velocity(primary: &Body, secondary: &Body, point: DVec2) -> DVec2 {
+    let [m1, m2] = [primary, secondary].map(|body| body.mass_kg);
+    let barycenter = (primary.position * m1 + secondary.position * m2) / (m1 + m2);
+    let drift = (primary.velocity * m1 + secondary.velocity * m2) / (m1 + m2);
+    let offset = secondary.position - primary.position;
+    let angular_velocity = offset.cross(secondary.velocity - primary.velocity) / offset.length_sq();
+    let arm = point - barycenter;
+    drift + dvec2(-arm.y, arm.x) * angular_velocity
+}
+
+/// The distance from `primary` inside which tides would pull `satellite` apart, in meters, were
+/// it a fluid held together only by its own gravity: `2.44 R (ρ_primary / ρ_satellite)^(1/3)`,
+/// with densities from each body's mass and radius.
+pub fn roche_limit(primary: &Body, satellite: &Body) -> f64 {
+    let density = |body: &Body| body.mass_kg / (body.radius_km as f64).powi(3);
+    2.44 * primary.radius_km as f64 * 1e3 * (density(primary) / density(satellite)).cbrt()
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new(scenario(Scenario::SolarSystem))
+    }
+}
+
+impl Simulation {
+    /// One hour.
+    pub const DEFAULT_TIMESTEP: f64 = 3600.;
+    /// Most steps that one call to [`Self::advance`] may run, so that a long stall is dropped
+    /// rather than caught up on all at once.
+    const MAX_STEPS_PER_ADVANCE: usize = 500;
+    pub const DEFAULT_OPENING_ANGLE: f64 = 0.5;
+    /// Below this many bodies, summing over every pair is about as fast and exact.
+    pub const DEFAULT_BARNES_HUT_MIN_BODIES: usize = 1_000;
+    pub const DEFAULT_MIN_TIMESTEP: f64 = 10.;
+    pub const DEFAULT_MAX_TIMESTEP: f64 = 24. * Self::DEFAULT_TIMESTEP;
+    /// Fraction of the shortest encounter time `r / v` that an adaptive step lasts.
+    const ADAPTIVE_STEP_FRACTION: f64 = 0.02;
+
+    /// About three months.
+    pub const DEFAULT_TRAIL_DURATION_S: f64 = 90. * 24. * Self::DEFAULT_TIMESTEP;
+
+    pub fn new(mut bodies: Vec<Body>) -> Self {
+        for body in &mut bodies {
+            body.trail.clear();
+        }
+        Self {
+            bodies: World::new(bodies),
+            trail_duration_s: Self::DEFAULT_TRAIL_DURATION_S,
+            collisions: Collisions::default(),
+            softening_m: 0.,
+            relativity: false,
+            barnes_hut: true,
+            barnes_hut_min_bodies: Self::DEFAULT_BARNES_HUT_MIN_BODIES,
+            opening_angle: Self::DEFAULT_OPENING_ANGLE,
+            adaptive_timestep: false,
+            timestep_s: Self::DEFAULT_TIMESTEP,
+            min_timestep_s: Self::DEFAULT_MIN_TIMESTEP,
+            max_timestep_s: Self::DEFAULT_MAX_TIMESTEP,
+            trajectory: None,
+            accumulator: 0.,
+            elapsed_seconds: 0.,
+        }
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        self.bodies.bodies()
+    }
+
+    /// The bodies, to change in place; see [`World::bodies_mut`].
+    pub fn bodies_mut(&mut self) -> &mut [Body] {
+        self.bodies.bodies_mut()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.bodies
+    }
+
+    /// Starts recording a [`Trajectory`] of `bodies`, sampled every `interval_steps` steps, in
+    /// place of any already being recorded.
+    pub fn record_trajectory(&mut self, bodies: &[BodyId], interval_steps: usize) {
+        self.trajectory = Some(Trajectory::new(
+            bodies,
+            interval_steps,
+            self.bodies(),
+            self.gravity(),
+            self.elapsed_seconds,
+        ));
+    }
+
+    /// A copy with bodies of its own, with the same ids, so that stepping it leaves this one
+    /// untouched. Trails and the trajectory being recorded are not copied.
+    pub fn duplicate(&self) -> Self {
+        let bodies = self
+            .bodies()
+            .iter()
+            .map(|body| {
+                Body::new(
+                    &body.name,
+                    body.mass_kg,
+                    body.radius_km,
+                    body.position,
+                    body.velocity,
+                    body.color,
+                )
+                .inclined(body.inclination_deg)
+                .gravitating(body.gravitates)
+                .piloted(body.spacecraft.clone())
+                .identified(body.id)
+            })
+            .collect();
+        Self {
+            bodies: World::new(bodies),
+            trail_duration_s: self.trail_duration_s,
+            collisions: self.collisions,
+            softening_m: self.softening_m,
+            relativity: self.relativity,
+            barnes_hut: self.barnes_hut,
+            barnes_hut_min_bodies: self.barnes_hut_min_bodies,
+            opening_angle: self.opening_angle,
+            adaptive_timestep: self.adaptive_timestep,
+            timestep_s: self.timestep_s,
+            min_timestep_s: self.min_timestep_s,
+            max_timestep_s: self.max_timestep_s,
+            trajectory: None,
+            accumulator: self.accumulator,
+            elapsed_seconds: self.elapsed_seconds,
+        }
+    }
+
+    pub fn add(&mut self, body: Body) {
+        self.bodies.push(body);
+    }
+
+    pub fn remove(&mut self, id: BodyId) -> Option<Body> {
+        self.bodies.remove(id)
+    }
+
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed_seconds
+    }
+
+    pub fn set_elapsed_seconds(&mut self, elapsed_seconds: f64) {
+        self.elapsed_seconds = elapsed_seconds;
+    }
+
+    /// Advances the simulation by `dt` seconds in fixed [`Self::timestep_s`]s, carrying the
+    /// remainder over to the next call so that results don't depend on the frame rate. Returns
+    /// the mergers that happened along the way, in order.
+    ///
+    /// A negative `dt` runs time backwards, with negative steps. A time-symmetric integrator such
+    /// as [`Integrator::Verlet`] then retraces the bodies' paths closely, but mergers can't be
+    /// undone.
+    pub fn advance(&mut self, dt: f64, integrator: Integrator) -> Vec<Merger> {
+        self.advance_while(dt, integrator, || true)
+    }
+
+    /// Like [`Self::advance`], but stops early once `keep_going` returns false after a step,
+    /// such as when a frame's time for physics is up. The time not yet stepped through is kept
+    /// for the next call, so a simulation too heavy to keep up slows down without its results
+    /// changing.
+    pub fn advance_while(
+        &mut self,
+        dt: f64,
+        integrator: Integrator,
+        mut keep_going: impl FnMut() -> bool,
+    ) -> Vec<Merger> {
+        // The remainder carried over is a length of time, whichever way it runs.
+        let (direction, dt) = (dt.signum(), dt.abs());
+        if self.adaptive_timestep {
+            return self.advance_adaptively(direction, dt, integrator, keep_going);
+        }
+        let max_accumulated = Self::MAX_STEPS_PER_ADVANCE as f64 * self.timestep_s;
+        self.accumulator = (self.accumulator + dt).min(max_accumulated);
+        let mut mergers = Vec::new();
+        while self.accumulator >= self.timestep_s {
+            mergers.extend(self.step(direction * self.timestep_s, integrator));
+            self.accumulator -= self.timestep_s;
+            if !keep_going() {
+                break;
+            }
+        }
+        mergers
+    }
+
+    /// Steps through exactly `seconds` of simulated time, however many steps it takes, ending
+    /// with a shorter step if need be, for running without a display to keep up with. Steps are
+    /// [`Self::timestep_s`] long, or chosen as by [`Self::advance`] with the adaptive timestep.
+    /// A negative `seconds` runs time backwards.
+    pub fn run_for(&mut self, seconds: f64, integrator: Integrator) -> Vec<Merger> {
+        self.run_for_while(seconds, integrator, || true)
+    }
+
+    /// Like [`Self::run_for`], but stops early once `keep_going` returns false after a step, to
+    /// be continued with the time left over.
+    pub fn run_for_while(
+        &mut self,
+        seconds: f64,
+        integrator: Integrator,
+        mut keep_going: impl FnMut() -> bool,
+    ) -> Vec<Merger> {
+        let (direction, mut remaining) = (seconds.signum(), seconds.abs());
+        let mut mergers = Vec::new();
+        while remaining > 0. {
+            let dt = if self.adaptive_timestep {
+                self.adaptive_timestep()
+            } else {
+                self.timestep_s
+            }
+            .min(remaining);
+            mergers.extend(self.step(direction * dt, integrator));
+            remaining -= dt;
+            if !keep_going() {
+                break;
+            }
+        }
+        mergers
+    }
+
+    /// How far back towards their states before the last fixed step to draw the bodies, as a
+    /// fraction of it, for them to move smoothly even with fewer steps than frames. The time
+    /// [`Self::advance`] carries over puts the present partway through the next step, so drawn
+    /// between their last two states, the bodies run one step behind it. Zero with the adaptive
+    /// timestep, whose steps are short where it matters.
+    pub fn step_lag(&self) -> f64 {
+        if self.adaptive_timestep {
+            0.
+        } else {
+            (1. - self.accumulator / self.timestep_s).clamp(0., 1.)
+        }
+    }
+
+    /// Like [`Self::advance`], but in steps of [`Self::adaptive_timestep`]. Steps never run past
+    /// the time accumulated so far, and once [`Self::MAX_STEPS_PER_ADVANCE`] have run any time
+    /// left over is dropped, so that the simulation slows down rather than stalls during a close
+    /// encounter.
+    fn advance_adaptively(
+        &mut self,
+        direction: f64,
+        dt: f64,
+        integrator: Integrator,
+        mut keep_going: impl FnMut() -> bool,
+    ) -> Vec<Merger> {
+        let max_accumulated = Self::MAX_STEPS_PER_ADVANCE as f64 * self.max_timestep_s;
+        self.accumulator = (self.accumulator + dt).min(max_accumulated);
+        let mut mergers = Vec::new();
+        let mut steps = 0;
+        while self.accumulator >= self.min_timestep_s && steps < Self::MAX_STEPS_PER_ADVANCE {
+            let dt = self.adaptive_timestep().min(self.accumulator);
+            mergers.extend(self.step(direction * dt, integrator));
+            self.accumulator -= dt;
+            steps += 1;
+            if !keep_going() {
+                break;
+            }
+        }
+        if steps == Self::MAX_STEPS_PER_ADVANCE {
+            self.accumulator = 0.;
+        }
+        mergers
+    }
+
+    /// A step short enough to resolve the closest encounter: a small fraction of the least time
+    /// `r / v` that any pair of bodies would take to close their distance at their relative
+    /// speed, within [`Self::min_timestep_s`] and [`Self::max_timestep_s`].
+    pub fn adaptive_timestep(&self) -> f64 {
+        let mut encounter_time = f64::INFINITY;
+        let bodies = self.bodies();
+        for (i, a) in bodies.iter().enumerate() {
+            for b in &bodies[i + 1..] {
+                // Test particles can pass each other as closely as they like.
+                if !a.gravitates && !b.gravitates {
+                    continue;
+                }
+                let distance = (b.position - a.position).length();
+                let speed = (b.velocity - a.velocity).length();
+                if speed > 0. {
+                    encounter_time = encounter_time.min(distance / speed);
+                }
+            }
+        }
+        (Self::ADAPTIVE_STEP_FRACTION * encounter_time).clamp(
+            self.min_timestep_s,
+            self.max_timestep_s.max(self.min_timestep_s),
+        )
+    }
+
+    /// Advances every body by `dt` seconds under the gravity of all the others, then merges or
+    /// bounces any that collided, as [`Self::collisions`] says. A negative `dt` steps backwards
+    /// in time.
+    pub fn step(&mut self, dt: f64, integrator: Integrator) -> Vec<Merger> {
+        let gravity = self.gravity();
+        for body in self.bodies_mut() {
+            body.previous_position = Some(body.position);
+        }
+        integrator.step(self.bodies_mut(), dt, gravity);
+        self.elapsed_seconds += dt;
+        let mergers = match self.collisions {
+            Collisions::PassThrough => Vec::new(),
+            Collisions::Merge => self.merge_collisions(),
+            Collisions::Bounce => {
+                self.bounce_collisions();
+                Vec::new()
+            }
+        };
+        if dt > 0. {
+            self.burn_maneuvers();
+        }
+        let (elapsed_seconds, trail_duration_s) = (self.elapsed_seconds, self.trail_duration_s);
+        for body in self.bodies_mut() {
+            body.record_trail(elapsed_seconds, trail_duration_s, dt < 0.);
+        }
+        let gravity = self.gravity();
+        if let Some(trajectory) = &mut self.trajectory {
+            for merger in &mergers {
+                trajectory.follow_merger(merger);
+            }
+            trajectory.record(self.bodies.bodies(), gravity, elapsed_seconds);
+        }
+        mergers
+    }
+
+    /// Makes the spacecraft's burns that have come due, each along its directions at the end of
+    /// the step that reached it. Running time back undoes none of them.
+    fn burn_maneuvers(&mut self) {
+        let elapsed_seconds = self.elapsed_seconds;
+        for index in 0..self.bodies.len() {
+            let Some(spacecraft) = &mut self.bodies_mut()[index].spacecraft else {
+                continue;
+            };
+            for maneuver in spacecraft.take_due(elapsed_seconds) {
+                let bodies = self.bodies();
+                let body = &bodies[index];
+                let (position, velocity) = match self.primary_of(index) {
+                    Some(primary) => {
+                        let primary = &bodies[primary];
+                        (
+                            body.position - primary.position,
+                            body.velocity - primary.velocity,
+                        )
+                    }
+                    None => (body.position, body.velocity),
+                };
+                let (prograde, radial) = maneuver_axes(position, velocity);
+                let change = prograde * maneuver.delta_v.x + radial * maneuver.delta_v.y;
+                let body = &mut self.bodies_mut()[index];
+                let Some(spacecraft) = &mut body.spacecraft else {
+                    break;
+                };
+                let wanted = change.length();
+                if wanted > 0. {
+                    let made = spacecraft.burn(wanted);
+                    body.velocity += change * (made / wanted);
+                }
+                body.mass_kg = spacecraft.mass_kg();
+            }
+        }
+    }
+
+    /// Whether gravity is approximated with a [`QuadTree`]; see [`Self::barnes_hut`].
+    pub fn uses_barnes_hut(&self) -> bool {
+        self.barnes_hut && self.bodies.len() >= self.barnes_hut_min_bodies
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        Gravity {
+            softening_m: self.softening_m,
+            relativity: self.relativity,
+            opening_angle: self.uses_barnes_hut().then_some(self.opening_angle),
+        }
+    }
+
+    /// Replaces each pair of overlapping bodies with their [`Body::merge`], checking the merged
+    /// body again against those that follow it.
+    fn merge_collisions(&mut self) -> Vec<Merger> {
+        let mut mergers = Vec::new();
+        if !self.any_overlap() {
+            return mergers;
+        }
+        for i in 0..self.bodies.len() {
+            let mut j = i + 1;
+            while j < self.bodies.len() {
+                let bodies = self.bodies();
+                if bodies[i].overlaps(&bodies[j]) {
+                    let merged = Body::merge(&bodies[i], &bodies[j]);
+                    let id = merged.id;
+                    let b = self.bodies.remove_at(j);
+                    let a = self.bodies.replace_at(i, merged);
+                    mergers.push(Merger {
+                        originals: [a, b],
+                        merged: id,
+                    });
+                    j = i + 1;
+                } else {
+                    j += 1;
+                }
+            }
+        }
+        mergers
+    }
+
+    /// Turns each pair of overlapping bodies that are still approaching each other apart, as an
+    /// elastic collision between spheres would. Pairs already separating are left alone, so that
+    /// a pair isn't bounced back together while it is still overlapping.
+    fn bounce_collisions(&mut self) {
+        if !self.any_overlap() {
+            return;
+        }
+        let bodies = self.bodies_mut();
+        for i in 0..bodies.len() {
+            let (before, after) = bodies.split_at_mut(i + 1);
+            let a = &mut before[i];
+            for b in after {
+                let offset = b.position - a.position;
+                if !a.overlaps(b) || offset == DVec2::ZERO {
+                    continue;
+                }
+                let normal = offset / offset.length();
+                let approach = (b.velocity - a.velocity).dot(normal);
+                if approach >= 0. {
+                    continue;
+                }
+                let (m1, m2) = (a.mass_kg, b.mass_kg);
+                let impulse = normal * (2. * approach / (m1 + m2));
+                a.velocity += impulse * m2;
+                b.velocity -= impulse * m1;
+            }
+        }
+    }
+
+    /// Whether any two bodies overlap. Sorting them along x first means each is only compared
+    /// with the few that are within reach of it along x, rather than with every other body.
+    fn any_overlap(&self) -> bool {
+        let max_radius_m = self
+            .bodies()
+            .iter()
+            .map(|body| body.radius_km)
+            .fold(0., f32::max) as f64
+            * 1e3;
+        let mut sorted: Vec<_> = self.bodies().iter().collect();
+        sorted.sort_by(|a, b| a.position.x.total_cmp(&b.position.x));
+        sorted.iter().enumerate().any(|(i, a)| {
+            let reach = a.radius_km as f64 * 1e3 + max_radius_m;
+            sorted[i + 1..]
+                .iter()
+                .take_while(|b| b.position.x - a.position.x < reach)
+                .any(|b| a.overlaps(b))
+        })
+    }
+
+    /// The mass-weighted mean position of all bodies, or the origin if there are none.
+    pub fn barycenter(&self) -> DVec2 {
+        let mass_kg: f64 = self.bodies().iter().map(|body| body.mass_kg).sum();
+        if mass_kg <= 0. {
+            return DVec2::ZERO;
+        }
+        self.bodies().iter().fold(DVec2::ZERO, |sum, body| {
+            sum + body.position * (body.mass_kg / mass_kg)
+        })
+    }
+
+    /// Kinetic and potential energy of the whole system.
+    pub fn energy(&self) -> Energy {
+        Energy {
+            kinetic_j: self
+                .bodies()
+                .iter()
+                .map(|body| 0.5 * body.mass_kg * body.velocity.length_sq())
+                .sum(),
+            potential_j: potential_energy(self.bodies(), self.softening_m),
+        }
+    }
+
+    /// Total linear momentum, in kg m/s.
+    pub fn momentum(&self) -> DVec2 {
+        self.bodies()
+            .iter()
+            .fold(DVec2::ZERO, |sum, body| sum + body.velocity * body.mass_kg)
+    }
+
+    /// Total angular momentum about the origin, in kg m²/s, positive counter-clockwise.
+    pub fn angular_momentum(&self) -> f64 {
+        self.bodies()
+            .iter()
+            .map(|body| body.mass_kg * body.position.cross(body.velocity))
+            .sum()
+    }
+
+    pub fn body(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.get(id)
+    }
+
+    pub fn body_mut(&mut self, id: BodyId) -> Option<&mut Body> {
+        self.bodies.get_mut(id)
+    }
+
+    pub fn index_of(&self, id: BodyId) -> Option<usize> {
+        self.bodies.index_of(id)
+    }
+
+    /// The lightest heavier body that the body at `index` is gravitationally bound to, if any.
+    /// Test particles, which don't pull on anything, are nobody's primary.
+    pub fn primary_of(&self, index: usize) -> Option<usize> {
+        let body = &self.bodies()[index];
+        self.bodies()
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| {
+                other.gravitates && other.mass_kg > body.mass_kg && body.is_bound_to(other)
+            })
+            .min_by(|(_, a), (_, b)| a.mass_kg.total_cmp(&b.mass_kg))
+            .map(|(i, _)| i)
+    }
+
+    /// [`Self::primary_of`] every body, in order. Each body is only checked against the bodies
+    /// heavier than it, so that many light bodies around a few heavy ones are quick to sort out.
+    pub fn primaries(&self) -> Vec<Option<usize>> {
+        let mass = |index: usize| self.bodies()[index].mass_kg;
+        let mut lightest_first: Vec<_> = (0..self.bodies.len()).collect();
+        lightest_first.sort_by(|&a, &b| mass(a).total_cmp(&mass(b)));
+        (0..self.bodies.len())
+            .map(|index| {
+                let heavier = lightest_first.partition_point(|&other| mass(other) <= mass(index));
+                lightest_first[heavier..].iter().copied().find(|&other| {
+                    self.bodies()[other].gravitates
+                        && self.bodies()[index].is_bound_to(&self.bodies()[other])
+                })
+            })
+            .collect()
+    }
+
+    /// Indices of the bodies whose primary is the body at `index`.
+    pub fn satellites_of(&self, index: usize) -> Vec<usize> {
+        (0..self.bodies.len())
+            .filter(|&i| self.primary_of(i) == Some(index))
+            .collect()
+    }
+}
+
+/// The gravitational acceleration of each of `bodies` due to all the others, were they at
+/// `positions` instead of where they are. Each pair's interaction is computed once and applied
+/// to both bodies in opposite directions. Bodies that don't [`Body::gravitates`] are pulled by
+/// the rest but pull on nothing, so they only add `O(n)` to the `O(n²)` cost.
+///
+/// With a nonzero `softening_m`, the `1/r²` force is replaced by `r / (r² + ε²)^(3/2)`, which
+/// matches it at long range but falls smoothly to zero as bodies pass through each other.
+///
+/// Off the web, and with a few hundred bodies or more, the pairs are shared out between
+/// threads, each adding up its own buffer of accelerations, and the buffers are summed after.
+pub fn accelerations(bodies: &[Body], positions: &[DVec2], softening_m: f64) -> Vec<DVec2> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if bodies.len() >= PARALLEL_MIN_BODIES {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        return accelerations_on(threads, bodies, positions, softening_m);
+    }
+    accelerations_on(1, bodies, positions, softening_m)
+}
+
+/// [`accelerations`], shared out between `threads`.
+fn accelerations_on(
+    threads: usize,
+    bodies: &[Body],
+    positions: &[DVec2],
+    softening_m: f64,
+) -> Vec<DVec2> {
+    let softening_sq = softening_m * softening_m;
+    // The acceleration at `position` towards a kilogram at `source`.
+    let field = |position: DVec2, source: DVec2| {
+        let offset = source - position;
+        let distance = offset.length();
+        if distance == 0. {
+            return DVec2::ZERO;
+        }
+        let softened = (distance * distance + softening_sq).sqrt();
+        offset / softened * G / (distance.max(MIN_GRAVITY_DISTANCE).powi(2) + softening_sq)
+    };
+    let mass = |i: usize| bodies[i].mass_kg;
+    // Test particles only feel the massive bodies, so they cost nothing among themselves.
+    let (massive, test_particles): (Vec<_>, Vec<_>) =
+        (0..bodies.len()).partition(|&i| bodies[i].gravitates);
+    // Adds the pulls of every `stride`th massive body from the `first` on, on the bodies after
+    // it and on the test particles.
+    let add_pulls = |first: usize, stride: usize, accelerations: &mut [DVec2]| {
+        for (k, &i) in massive.iter().enumerate().skip(first).step_by(stride) {
+            for &j in &massive[k + 1..] {
+                let field = field(positions[i], positions[j]);
+                accelerations[i] += field * mass(j);
+                accelerations[j] -= field * mass(i);
+            }
+            for &j in &test_particles {
+                accelerations[j] += field(positions[j], positions[i]) * mass(i);
+            }
+        }
+    };
+    let mut accelerations = vec![DVec2::ZERO; bodies.len()];
+    // The web has only the one thread.
+    if cfg!(not(target_arch = "wasm32")) && threads > 1 {
+        // Taking every `threads`th body, rather than a run of them, evens out the work: the
+        // earlier ones have more pairs after them.
+        let mut buffers = vec![vec![DVec2::ZERO; bodies.len()]; threads];
+        std::thread::scope(|scope| {
+            for (first, buffer) in buffers.iter_mut().enumerate() {
+                let add_pulls = &add_pulls;
+                scope.spawn(move || add_pulls(first, threads, buffer));
+            }
+        });
+        for buffer in buffers {
+            for (acceleration, part) in accelerations.iter_mut().zip(buffer) {
+                *acceleration += part;
+            }
+        }
+        return accelerations;
+    }
+    add_pulls(0, 1, &mut accelerations);
+    accelerations
+}
+
+/// The first post-Newtonian correction to the acceleration of each of `bodies`, were they at
+/// `positions` moving at `velocities`. For each pair this is the test-particle form
+/// `GM / (c² r³) ((4GM/r - v²) r + 4 (r·v) v)` in their relative position and velocity, which
+/// advances perihelia by general relativity's `6πGM / (c² a (1 - e²))` per orbit, about 43″ a
+/// century for Mercury. Meant to be added to [`accelerations`].
+pub fn relativistic_accelerations(
+    bodies: &[Body],
+    positions: &[DVec2],
+    velocities: &[DVec2],
+) -> Vec<DVec2> {
+    let mut accelerations = vec![DVec2::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            if !bodies[i].gravitates && !bodies[j].gravitates {
+                continue;
+            }
+            let offset = positions[i] - positions[j];
+            let velocity = velocities[i] - velocities[j];
+            let distance = offset.length().max(MIN_GRAVITY_DISTANCE);
+            let radial_speed = offset.x * velocity.x + offset.y * velocity.y;
+            // The two bodies see the same relative orbit, mirrored, each around the other's mass.
+            let correction = |mu: f64| {
+                (offset * (4. * mu / distance - velocity.length_sq())
+                    + velocity * (4. * radial_speed))
+                    * (mu / (C * C * distance.powi(3)))
+            };
+            if bodies[j].gravitates {
+                accelerations[i] += correction(G * bodies[j].mass_kg);
+            }
+            if bodies[i].gravitates {
+                accelerations[j] -= correction(G * bodies[i].mass_kg);
+            }
+        }
+    }
+    accelerations
+}
+
+/// The gravitational potential energy of `bodies`, in joules, consistent with the softened and
+/// clamped forces of [`accelerations`] outside [`MIN_GRAVITY_DISTANCE`].
+fn potential_energy(bodies: &[Body], softening_m: f64) -> f64 {
+    let softening_sq = softening_m * softening_m;
+    let mut energy = 0.;
+    for (i, a) in bodies.iter().enumerate() {
+        for b in &bodies[i + 1..] {
+            if !a.gravitates && !b.gravitates {
+                continue;
+            }
+            let distance = (b.position - a.position).length().max(MIN_GRAVITY_DISTANCE);
+            energy -= G * a.mass_kg * b.mass_kg / (distance * distance + softening_sq).sqrt();
+        }
+    }
+    energy
+}
+
+/// Gives the heaviest body whatever velocity cancels the system's net momentum, so the
+/// barycenter stays put instead of drifting off.
+pub fn remove_net_momentum(bodies: &mut [Body]) {
+    if let Some(heaviest) =
+        (0..bodies.len()).max_by(|&a, &b| bodies[a].mass_kg.total_cmp(&bodies[b].mass_kg))
+    {
+        let others = bodies
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != heaviest)
+            .fold(DVec2::ZERO, |sum, (_, body)| {
+                sum + body.velocity * body.mass_kg
+            });
+        let heaviest = &mut bodies[heaviest];
+        heaviest.velocity = -others / heaviest.mass_kg;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_S: f64 = 86_400.;
+
+    /// The Sun at the origin, at rest, and `body` around it.
+    fn with_sun(body: Body) -> Simulation {
+        let sun = Body::new(
+            "Sun",
+            SUN_MASS_KG,
+            696_000.,
+            DVec2::ZERO,
+            DVec2::ZERO,
+            Color32::YELLOW,
+        );
+        Simulation::new(vec![sun, body])
+    }
+
+    /// How far the second body is from the first.
+    fn separation(simulation: &Simulation) -> f64 {
+        let [a, b] = [0, 1].map(|index| simulation.bodies()[index].position);
+        (b - a).length()
+    }
+
+    #[test]
+    fn verlet_keeps_earth_on_its_orbit_for_a_year() {
+        let earth = Body::orbiting(
+            "Earth",
+            EARTH_MASS_KG,
+            6_371.,
+            1.496e8,
+            Color32::BLUE,
+            0.,
+            0.,
+        );
+        let mut simulation = with_sun(earth);
+        let start = separation(&simulation);
+        simulation.run_for(365.25 * DAY_S, Integrator::Verlet);
+        let end = separation(&simulation);
+        assert!(
+            (end - start).abs() < 0.01 * start,
+            "Earth went from {start} m to {end} m from the Sun"
+        );
+    }
+
+    #[test]
+    fn mercury_swings_between_its_periapsis_and_apoapsis() {
+        let (a, e): (f64, f64) = (57.9e9, 0.2056);
+        let mercury = Body::orbiting_elliptical(
+            "Mercury",
+            3.301e23,
+            2_440.,
+            57.9e6,
+            0.2056,
+            Color32::GRAY,
+            0.,
+        );
+        let mut simulation = with_sun(mercury);
+        let period_s = std::f64::consts::TAU * (a.powi(3) / (G * SUN_MASS_KG)).sqrt();
+        let (mut nearest, mut farthest) = (f64::INFINITY, 0f64);
+        while simulation.elapsed_seconds() < period_s {
+            simulation.step(Simulation::DEFAULT_TIMESTEP, Integrator::Verlet);
+            let distance = separation(&simulation);
+            nearest = nearest.min(distance);
+            farthest = farthest.max(distance);
+        }
+        for (found, expected) in [(nearest, a * (1. - e)), (farthest, a * (1. + e))] {
+            assert!(
+                (found - expected).abs() < 0.01 * expected,
+                "expected {expected} m from the Sun, found {found} m"
+            );
+        }
+    }
+
+    #[test]
+    fn accelerations_shared_between_threads_match_each_pairs_pull() {
+        // A few of them test particles.
+        let bodies: Vec<_> = (0..300)
+            .map(|i| {
+                let angle = i as f64 * 2.4;
+                let position = dvec2(angle.cos(), angle.sin()) * (1e9 + i as f64 * 1e8);
+                Body::new("", EARTH_MASS_KG, 1., position, DVec2::ZERO, Color32::WHITE)
+                    .gravitating(i % 7 != 0)
+            })
+            .collect();
+        let positions: Vec<_> = bodies.iter().map(|body| body.position).collect();
+        let found = accelerations_on(4, &bodies, &positions, 0.);
+        for (i, body) in bodies.iter().enumerate() {
+            let expected = bodies
+                .iter()
+                .filter(|other| other.gravitates && other.position != body.position)
+                .map(|other| {
+                    let offset = other.position - body.position;
+                    offset * (G * other.mass_kg / offset.length().powi(3))
+                })
+                .fold(DVec2::ZERO, |total, pull| total + pull);
+            assert!(
+                (found[i] - expected).length() < 1e-9 * expected.length(),
+                "body {i} was pulled by {:?}, expected {expected:?}",
+                found[i]
+            );
+        }
+    }
+
+    #[test]
+    fn relativity_advances_mercurys_perihelion() {
+        const ORBITS: f64 = 10.;
+        let (a, e): (f64, f64) = (57.9e9, 0.2056);
+        let mu = G * SUN_MASS_KG;
+        let period_s = std::f64::consts::TAU * (a.powi(3) / mu).sqrt();
+        let perihelion_after = |relativity: bool| {
+            let mercury = Body::orbiting_elliptical(
+                "Mercury",
+                3.301e23,
+                2_440.,
+                57.9e6,
+                0.2056,
+                Color32::GRAY,
+                0.,
+            );
+            let mut simulation = with_sun(mercury);
+            simulation.relativity = relativity;
+            simulation.timestep_s = 600.;
+            simulation.run_for(ORBITS * period_s, Integrator::Verlet);
+            let [sun, mercury] = [0, 1].map(|index| &simulation.bodies()[index]);
+            mercury.orbital_elements(sun).argument_of_periapsis_deg
+        };
+        // The integrator's own drift is the same either way, and cancels. Periapsis starts on
+        // the x axis, so either may have wrapped around past 360°.
+        let difference = perihelion_after(true) - perihelion_after(false);
+        let advance = ((difference + 180.).rem_euclid(360.) - 180.).to_radians() / ORBITS;
+        let expected = 6. * std::f64::consts::PI * mu / (C * C * a * (1. - e * e));
+        assert!(
+            (advance - expected).abs() < 0.05 * expected,
+            "perihelion advanced {advance} rad an orbit, expected {expected}"
+        );
+    }
+}
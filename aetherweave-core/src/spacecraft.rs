@@ -0,0 +1,107 @@
+//! Spacecraft: bodies that carry propellant and an engine, and burn at planned maneuver nodes.
+
+use crate::vector::{dvec2, DVec2};
+
+/// In m/s², for converting specific impulse to exhaust velocity.
+pub const STANDARD_GRAVITY: f64 = 9.806_65;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Spacecraft {
+    pub dry_mass_kg: f64,
+    pub propellant_kg: f64,
+    /// Specific impulse, in seconds: the engine's exhaust velocity over standard gravity.
+    pub specific_impulse_s: f64,
+    /// In newtons. Burns happen all at once, at their nodes; this only says how long a real one
+    /// would take.
+    pub thrust_n: f64,
+    /// Burns to make, soonest first.
+    pub maneuvers: Vec<Maneuver>,
+}
+
+/// A burn planned for a moment along a spacecraft's path.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Maneuver {
+    /// When to burn, in the simulation's elapsed seconds.
+    pub elapsed_seconds: f64,
+    /// The change in velocity, in m/s: `x` prograde, along the velocity relative to the body
+    /// orbited, and `y` radially out, across it away from that body.
+    pub delta_v: DVec2,
+}
+
+impl Spacecraft {
+    /// A spacecraft of `mass_kg`, two-thirds of it propellant, with an engine like that of an
+    /// upper stage.
+    pub fn with_mass(mass_kg: f64) -> Self {
+        Self {
+            dry_mass_kg: mass_kg / 3.,
+            propellant_kg: mass_kg * 2. / 3.,
+            specific_impulse_s: 320.,
+            thrust_n: 2e4,
+            maneuvers: Vec::new(),
+        }
+    }
+
+    pub fn mass_kg(&self) -> f64 {
+        self.dry_mass_kg + self.propellant_kg
+    }
+
+    fn exhaust_velocity(&self) -> f64 {
+        self.specific_impulse_s * STANDARD_GRAVITY
+    }
+
+    /// The change in speed the propellant left is good for, by Tsiolkovsky's rocket equation.
+    pub fn delta_v_budget(&self) -> f64 {
+        self.exhaust_velocity() * (self.mass_kg() / self.dry_mass_kg).ln()
+    }
+
+    /// The propellant burned to change speed by `delta_v`, if there is that much.
+    pub fn propellant_for(&self, delta_v: f64) -> f64 {
+        self.mass_kg() * (1. - (-delta_v / self.exhaust_velocity()).exp())
+    }
+
+    /// How long the engine would take to change speed by `delta_v`, in seconds.
+    pub fn burn_duration_s(&self, delta_v: f64) -> f64 {
+        self.propellant_for(delta_v) * self.exhaust_velocity() / self.thrust_n
+    }
+
+    /// Adds `maneuver` to the plan, in order.
+    pub fn plan(&mut self, maneuver: Maneuver) {
+        let index = self
+            .maneuvers
+            .partition_point(|planned| planned.elapsed_seconds <= maneuver.elapsed_seconds);
+        self.maneuvers.insert(index, maneuver);
+    }
+
+    /// Takes the maneuvers due by `elapsed_seconds` off the plan, in order.
+    pub(crate) fn take_due(&mut self, elapsed_seconds: f64) -> Vec<Maneuver> {
+        let due = self
+            .maneuvers
+            .partition_point(|planned| planned.elapsed_seconds <= elapsed_seconds);
+        self.maneuvers.drain(..due).collect()
+    }
+
+    /// Burns to change speed by `delta_v`, or as near as the propellant allows, returning the
+    /// change made.
+    pub(crate) fn burn(&mut self, delta_v: f64) -> f64 {
+        let delta_v = delta_v.min(self.delta_v_budget());
+        self.propellant_kg = (self.propellant_kg - self.propellant_for(delta_v)).max(0.);
+        delta_v
+    }
+}
+
+/// The prograde and radially outward directions of a maneuver, for a spacecraft at
+/// `relative_position` and moving at `relative_velocity` relative to the body it orbits.
+pub fn maneuver_axes(relative_position: DVec2, relative_velocity: DVec2) -> (DVec2, DVec2) {
+    let speed = relative_velocity.length();
+    if speed == 0. {
+        return (dvec2(1., 0.), dvec2(0., 1.));
+    }
+    let prograde = relative_velocity / speed;
+    let across = dvec2(prograde.y, -prograde.x);
+    let radial = if across.dot(relative_position) < 0. {
+        -across
+    } else {
+        across
+    };
+    (prograde, radial)
+}
@@ -0,0 +1,254 @@
+use crate::{
+    simulation::{Body, Collisions, Simulation},
+    spacecraft::Spacecraft,
+    vector::DVec2,
+};
+use ecolor::Color32;
+use std::{fmt, path::Path};
+
+/// A scenario on disk, in RON with `Option`s written without `Some(…)`, e.g.
+///
+/// ```ron
+/// (bodies: [
+///     (name: "Sun", mass_kg: 1.9891e30, color: (255, 215, 0), position: (x: 0, y: 0)),
+///     (name: "Earth", mass_kg: 5.97e24, color: (0, 0, 255), orbital_radius_km: 1.5e8),
+/// ])
+/// ```
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct SystemFile {
+    /// Simulated seconds elapsed when the file was saved.
+    #[serde(default)]
+    pub elapsed_seconds: f64,
+    /// Julian date at which the simulation starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<f64>,
+    /// Simulated seconds per real second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<SystemView>,
+    /// What happens when bodies touch. Defaults to merging them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collisions: Option<Collisions>,
+    pub bodies: Vec<BodyFile>,
+}
+
+/// What part of the system was in view when the file was saved.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct SystemView {
+    /// In plot coordinates, which are meters unless distances are drawn on a log scale.
+    pub center: [f32; 2],
+    /// Meters per screen point.
+    pub scale: f32,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct BodyFile {
+    pub name: String,
+    pub mass_kg: f64,
+    #[serde(default = "default_radius_km")]
+    pub radius_km: f32,
+    /// Red, green and blue.
+    pub color: [u8; 3],
+    /// In meters. Either this or `orbital_radius_km` must be given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<DVec2>,
+    /// Place the body on a circular orbit around the Sun at the origin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbital_radius_km: Option<f32>,
+    /// Angle of the orbital position, counter-clockwise from the positive x axis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degrees: Option<f32>,
+    /// In m/s. Defaults to a circular orbital velocity with `orbital_radius_km`, or rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity: Option<DVec2>,
+    /// Tilt of the orbital plane for drawing, in degrees. Defaults to none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inclination_deg: Option<f32>,
+    /// Whether the body pulls on the others, rather than being a test particle. Defaults to
+    /// true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gravitates: Option<bool>,
+    /// Propellant, engine and planned burns, if the body is a spacecraft.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spacecraft: Option<Spacecraft>,
+}
+
+/// Assumes an Earth-like density when the file doesn't say.
+fn default_radius_km() -> f32 {
+    6_371.
+}
+
+#[derive(Debug)]
+pub enum SystemFileError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// A body had neither a position nor an orbital radius.
+    Unplaced(String),
+}
+
+impl fmt::Display for SystemFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Couldn't access the file: {error}"),
+            Self::Parse(error) => write!(f, "Couldn't parse the file: {error}"),
+            Self::Serialize(error) => write!(f, "Couldn't write the system: {error}"),
+            Self::Unplaced(name) => write!(
+                f,
+                "\"{name}\" needs either a `position` or an `orbital_radius_km`"
+            ),
+        }
+    }
+}
+
+impl BodyFile {
+    fn from_body(body: &Body) -> Self {
+        let color = body.color;
+        Self {
+            name: body.name.clone(),
+            mass_kg: body.mass_kg,
+            radius_km: body.radius_km,
+            color: [color.r(), color.g(), color.b()],
+            position: Some(body.position),
+            orbital_radius_km: None,
+            degrees: None,
+            velocity: Some(body.velocity),
+            inclination_deg: Some(body.inclination_deg).filter(|&degrees| degrees != 0.),
+            gravitates: Some(body.gravitates).filter(|&gravitates| !gravitates),
+            spacecraft: body.spacecraft.clone(),
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        let [r, g, b] = self.color;
+        Color32::from_rgb(r, g, b)
+    }
+
+    fn to_body(&self) -> Result<Body, SystemFileError> {
+        let inclination_deg = self.inclination_deg.unwrap_or_default();
+        let mut body = match (self.position, self.orbital_radius_km) {
+            (Some(position), _) => Body::new(
+                &self.name,
+                self.mass_kg,
+                self.radius_km,
+                position,
+                DVec2::ZERO,
+                self.color(),
+            )
+            .inclined(inclination_deg),
+            (None, Some(orbital_radius_km)) => Body::orbiting(
+                &self.name,
+                self.mass_kg,
+                self.radius_km,
+                orbital_radius_km,
+                self.color(),
+                self.degrees.unwrap_or_default(),
+                inclination_deg,
+            ),
+            (None, None) => return Err(SystemFileError::Unplaced(self.name.clone())),
+        };
+        if let Some(velocity) = self.velocity {
+            body.velocity = velocity;
+        }
+        if let Some(spacecraft) = &self.spacecraft {
+            body.mass_kg = spacecraft.mass_kg();
+        }
+        Ok(body
+            .gravitating(self.gravitates.unwrap_or(true))
+            .piloted(self.spacecraft.clone()))
+    }
+}
+
+impl SystemFile {
+    pub fn from_simulation(simulation: &Simulation) -> Self {
+        Self {
+            elapsed_seconds: simulation.elapsed_seconds(),
+            epoch: None,
+            time_scale: None,
+            view: None,
+            collisions: Some(simulation.collisions)
+                .filter(|&collisions| collisions != Collisions::default()),
+            bodies: simulation
+                .bodies()
+                .iter()
+                .map(BodyFile::from_body)
+                .collect(),
+        }
+    }
+
+    pub fn to_simulation(&self) -> Result<Simulation, SystemFileError> {
+        let bodies = self
+            .bodies
+            .iter()
+            .map(BodyFile::to_body)
+            .collect::<Result<_, _>>()?;
+        let mut simulation = Simulation::new(bodies);
+        simulation.set_elapsed_seconds(self.elapsed_seconds);
+        simulation.collisions = self.collisions.unwrap_or_default();
+        Ok(simulation)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SystemFileError> {
+        ron_options().from_str(text).map_err(SystemFileError::Parse)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SystemFileError> {
+        ron_options()
+            .to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(SystemFileError::Serialize)
+    }
+}
+
+fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+}
+
+pub fn load_system(path: impl AsRef<Path>) -> Result<Simulation, SystemFileError> {
+    let text = std::fs::read_to_string(path).map_err(SystemFileError::Io)?;
+    SystemFile::parse(&text)?.to_simulation()
+}
+
+pub fn save_system(path: impl AsRef<Path>, simulation: &Simulation) -> Result<(), SystemFileError> {
+    let text = SystemFile::from_simulation(simulation).to_ron()?;
+    std::fs::write(path, text).map_err(SystemFileError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSTEM: &str = r#"(
+        elapsed_seconds: 86400.5,
+        collisions: Bounce,
+        bodies: [
+            (name: "Sun", mass_kg: 1.9891e30, color: (255, 215, 0), position: (x: 0, y: 0)),
+            (name: "Earth", mass_kg: 5.97e24, color: (0, 0, 255), orbital_radius_km: 1.5e8,
+                degrees: 30, inclination_deg: 7),
+            (name: "Probe", mass_kg: 1000, radius_km: 0.01, color: (255, 255, 255),
+                position: (x: 2e11, y: 0), velocity: (x: 0, y: 25000), gravitates: false,
+                spacecraft: (dry_mass_kg: 400, propellant_kg: 600, specific_impulse_s: 320,
+                    thrust_n: 20000, maneuvers: [(elapsed_seconds: 9e5, delta_v: (x: 10, y: -2))])),
+        ],
+    )"#;
+
+    /// Loads `text` into a simulation and saves it back.
+    fn round_trip(text: &str) -> String {
+        let simulation = SystemFile::parse(text).unwrap().to_simulation().unwrap();
+        SystemFile::from_simulation(&simulation).to_ron().unwrap()
+    }
+
+    #[test]
+    fn saving_a_loaded_system_keeps_it() {
+        let saved = round_trip(SYSTEM);
+        let file = SystemFile::parse(&saved).unwrap();
+        assert_eq!(file.elapsed_seconds, 86400.5);
+        assert_eq!(file.collisions, Some(Collisions::Bounce));
+        assert_eq!(file.bodies.len(), 3);
+        assert!(file.bodies[2]
+            .spacecraft
+            .as_ref()
+            .is_some_and(|craft| craft.maneuvers.len() == 1));
+        assert_eq!(round_trip(&saved), saved);
+    }
+}
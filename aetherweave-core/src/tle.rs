@@ -0,0 +1,223 @@
+//! Earth satellites from NORAD two-line element sets, as published by CelesTrak and Space-Track.
+
+use crate::{
+    calendar::DateTime,
+    ephemeris::true_anomaly_deg,
+    simulation::{OrbitalElements, EARTH_MASS_KG, G},
+};
+use std::fmt;
+
+/// Earth's equatorial radius and second zonal harmonic, as in WGS 72, which SGP4 and so the
+/// element sets assume.
+const EARTH_RADIUS_M: f64 = 6_378_135.;
+const J2: f64 = 1.082_616e-3;
+const SECONDS_PER_DAY: f64 = 86_400.;
+
+/// How a satellite is carried from its element set's epoch to the simulation's date.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Propagation {
+    /// The two-body orbit, unchanged.
+    Kepler,
+    /// SGP4's secular drift: the node and perigee turning under Earth's oblateness, and the
+    /// mean motion quickening with drag, without its periodic or deep-space terms.
+    #[default]
+    Secular,
+}
+
+impl Propagation {
+    pub const ALL: [Self; 2] = [Self::Kepler, Self::Secular];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kepler => "Keplerian",
+            Self::Secular => "Secular J2 and drag",
+        }
+    }
+}
+
+/// One satellite's mean elements, from lines 1 and 2 of its set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tle {
+    /// From the title line before the set, or the catalog number if there isn't one.
+    pub name: String,
+    /// Julian date (UTC) the elements are for.
+    pub epoch: f64,
+    /// Half the rate of change of the mean motion, in revolutions per day squared.
+    pub mean_motion_rate: f64,
+    pub inclination_deg: f64,
+    pub right_ascension_deg: f64,
+    pub eccentricity: f64,
+    pub argument_of_perigee_deg: f64,
+    pub mean_anomaly_deg: f64,
+    /// Revolutions per day.
+    pub mean_motion: f64,
+}
+
+#[derive(Debug)]
+pub enum TleError {
+    /// There was no pair of lines starting `1 ` and `2 `.
+    NoSets,
+    /// A line of `satellite`'s set fails its checksum, so it was probably mangled in copying.
+    Checksum { satellite: String, line: u8 },
+    /// `field` of `satellite`'s set isn't a number.
+    InvalidField {
+        satellite: String,
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for TleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSets => write!(f, "No two-line element sets, with lines starting 1 and 2"),
+            Self::Checksum { satellite, line } => {
+                write!(f, "Line {line} of \"{satellite}\" fails its checksum")
+            }
+            Self::InvalidField { satellite, field } => {
+                write!(f, "Couldn't read the {field} of \"{satellite}\"")
+            }
+        }
+    }
+}
+
+/// Every element set in `text`, in two-line or three-line (titled) form, one after another.
+pub fn parse_tles(text: &str) -> Result<Vec<Tle>, TleError> {
+    let lines: Vec<_> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let mut sets = Vec::new();
+    let mut index = 0;
+    while index + 1 < lines.len() {
+        let (line1, line2) = (lines[index], lines[index + 1]);
+        if !(line1.starts_with("1 ") && line2.starts_with("2 ")) {
+            index += 1;
+            continue;
+        }
+        let title = index
+            .checked_sub(1)
+            .map(|title| lines[title])
+            .filter(|title| !title.starts_with("2 "));
+        sets.push(Tle::parse(title, line1, line2)?);
+        index += 2;
+    }
+    if sets.is_empty() {
+        return Err(TleError::NoSets);
+    }
+    Ok(sets)
+}
+
+impl Tle {
+    fn parse(title: Option<&str>, line1: &str, line2: &str) -> Result<Self, TleError> {
+        let name = match title {
+            // Space-Track prefixes titles with a line number of 0.
+            Some(title) => title.strip_prefix("0 ").unwrap_or(title).trim().to_string(),
+            None => columns(line1, 3, 7).to_string(),
+        };
+        for (line, text) in [(1, line1), (2, line2)] {
+            if !checksum_matches(text) {
+                return Err(TleError::Checksum {
+                    satellite: name,
+                    line,
+                });
+            }
+        }
+        let number = |field, text: &str| {
+            text.trim()
+                .parse::<f64>()
+                .map_err(|_| TleError::InvalidField {
+                    satellite: name.clone(),
+                    field,
+                })
+        };
+        // Two-digit years: 57 to 99 are 1957 to 1999, the rest this century.
+        let year = number("epoch year", columns(line1, 19, 20))? as i32;
+        let year = if year < 57 { 2000 + year } else { 1900 + year };
+        let new_year = DateTime {
+            year,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+        };
+        let day_of_year = number("epoch day", columns(line1, 21, 32))?;
+        Ok(Self {
+            epoch: new_year.julian_date() + day_of_year - 1.,
+            mean_motion_rate: number("mean motion derivative", columns(line1, 34, 43))?,
+            inclination_deg: number("inclination", columns(line2, 9, 16))?,
+            right_ascension_deg: number("right ascension", columns(line2, 18, 25))?,
+            // With its leading decimal point left out.
+            eccentricity: number("eccentricity", &format!("0.{}", columns(line2, 27, 33)))?,
+            argument_of_perigee_deg: number("argument of perigee", columns(line2, 35, 42))?,
+            mean_anomaly_deg: number("mean anomaly", columns(line2, 44, 51))?,
+            mean_motion: number("mean motion", columns(line2, 53, 63))?,
+            name,
+        })
+    }
+
+    /// The orbit around Earth on `julian_date`, carried there from the set's epoch by
+    /// `propagation`, flattened onto the equator with its periapsis at its longitude of perigee.
+    pub fn elements_on(&self, julian_date: f64, propagation: Propagation) -> OrbitalElements {
+        let days = julian_date - self.epoch;
+        let (mean_motion, revolutions) = match propagation {
+            Propagation::Kepler => (self.mean_motion, self.mean_motion * days),
+            Propagation::Secular => (
+                self.mean_motion + 2. * self.mean_motion_rate * days,
+                self.mean_motion * days + self.mean_motion_rate * days * days,
+            ),
+        };
+        let radians_per_second = mean_motion * std::f64::consts::TAU / SECONDS_PER_DAY;
+        let semi_major_axis_m = (G * EARTH_MASS_KG / radians_per_second.powi(2)).cbrt();
+        let e = self.eccentricity;
+        let mut longitude_of_perigee_deg = self.right_ascension_deg + self.argument_of_perigee_deg;
+        let mut mean_anomaly_deg = self.mean_anomaly_deg + 360. * revolutions;
+        if propagation == Propagation::Secular {
+            // The first-order rates from J2, as in Vallado's "Fundamentals of Astrodynamics", in
+            // degrees over the `days`.
+            let p = semi_major_axis_m * (1. - e * e);
+            let rate = 0.75 * J2 * (EARTH_RADIUS_M / p).powi(2) * 360. * mean_motion * days;
+            let cos_i = self.inclination_deg.to_radians().cos();
+            let node = -2. * rate * cos_i;
+            let perigee = rate * (5. * cos_i * cos_i - 1.);
+            longitude_of_perigee_deg += node + perigee;
+            mean_anomaly_deg += rate * (1. - e * e).sqrt() * (3. * cos_i * cos_i - 1.);
+        }
+        let true_anomaly_deg = true_anomaly_deg(mean_anomaly_deg, e);
+        OrbitalElements {
+            semi_major_axis_m,
+            eccentricity: e,
+            inclination_deg: self.inclination_deg as f32,
+            argument_of_periapsis_deg: longitude_of_perigee_deg.rem_euclid(360.),
+            true_anomaly_deg,
+            period_s: Some(std::f64::consts::TAU / radians_per_second),
+            distance_m: semi_major_axis_m * (1. - e * e)
+                / (1. + e * true_anomaly_deg.to_radians().cos()),
+        }
+    }
+}
+
+/// The text in the 1-based, inclusive columns `first` to `last` of `line`, as the format gives
+/// them, or as much of it as there is.
+fn columns(line: &str, first: usize, last: usize) -> &str {
+    let end = last.min(line.len());
+    line.get(first - 1..end).unwrap_or_default()
+}
+
+/// Whether the last column of `line` is the sum of its digits, counting each minus sign as 1,
+/// modulo 10.
+fn checksum_matches(line: &str) -> bool {
+    let Some(expected) = line.chars().nth(68).and_then(|digit| digit.to_digit(10)) else {
+        // A set trimmed to 68 columns has nothing to check.
+        return line.len() == 68;
+    };
+    let sum: u32 = line
+        .chars()
+        .take(68)
+        .map(|c| match c {
+            '-' => 1,
+            c => c.to_digit(10).unwrap_or(0),
+        })
+        .sum();
+    sum % 10 == expected
+}
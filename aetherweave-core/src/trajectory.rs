@@ -0,0 +1,138 @@
+use crate::{
+    simulation::{Body, BodyId, Gravity, Merger},
+    vector::DVec2,
+};
+use std::{borrow::Cow, fmt::Write as _, path::Path};
+
+/// A record of some bodies' states, sampled every `interval_steps` physics steps while
+/// `recording`, for export as CSV.
+pub struct Trajectory {
+    bodies: Vec<BodyId>,
+    pub recording: bool,
+    pub interval_steps: usize,
+    steps_until_sample: usize,
+    samples: Vec<Sample>,
+}
+
+struct Sample {
+    elapsed_seconds: f64,
+    name: String,
+    position: DVec2,
+    velocity: DVec2,
+    acceleration: DVec2,
+}
+
+impl Trajectory {
+    /// Starts recording `bodies`, of those in a simulation of `all`, taking the first samples
+    /// right away.
+    pub fn new(
+        bodies: &[BodyId],
+        interval_steps: usize,
+        all: &[Body],
+        gravity: Gravity,
+        elapsed_seconds: f64,
+    ) -> Self {
+        let mut trajectory = Self {
+            bodies: bodies.to_vec(),
+            recording: true,
+            interval_steps,
+            steps_until_sample: 0,
+            samples: Vec::new(),
+        };
+        trajectory.record(all, gravity, elapsed_seconds);
+        trajectory
+    }
+
+    /// Whether `body` is the only one being recorded.
+    pub fn is_of(&self, body: BodyId) -> bool {
+        self.bodies == [body]
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Called after every physics step of a simulation of `bodies`, moved by `gravity`; samples
+    /// the recorded ones when the interval is up.
+    pub fn record(&mut self, bodies: &[Body], gravity: Gravity, elapsed_seconds: f64) {
+        if !self.recording {
+            return;
+        }
+        if self.steps_until_sample > 0 {
+            self.steps_until_sample -= 1;
+            return;
+        }
+        let positions: Vec<_> = bodies.iter().map(|body| body.position).collect();
+        let velocities: Vec<_> = bodies.iter().map(|body| body.velocity).collect();
+        let accelerations = gravity.accelerations(bodies, &positions, &velocities);
+        for id in &self.bodies {
+            if let Some(index) = bodies.iter().position(|body| body.id == *id) {
+                self.samples.push(Sample {
+                    elapsed_seconds,
+                    name: bodies[index].name.clone(),
+                    position: positions[index],
+                    velocity: velocities[index],
+                    acceleration: accelerations[index],
+                });
+            }
+        }
+        self.steps_until_sample = self.interval_steps.saturating_sub(1);
+    }
+
+    /// Keeps recording the merged body if a recorded one was absorbed.
+    pub fn follow_merger(&mut self, merger: &Merger) {
+        let absorbed = |id: &BodyId| merger.originals.iter().any(|original| original.id == *id);
+        if self.bodies.iter().any(absorbed) {
+            self.bodies.retain(|id| !absorbed(id));
+            self.bodies.push(merger.merged);
+        }
+    }
+
+    /// One row per body per sample, in seconds, meters, m/s and m/s².
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time_s,body,x_m,y_m,vx_m_s,vy_m_s,ax_m_s2,ay_m_s2\n");
+        for Sample {
+            elapsed_seconds,
+            name,
+            position,
+            velocity,
+            acceleration,
+        } in &self.samples
+        {
+            // Writing to a `String` can't fail.
+            let _ = writeln!(
+                csv,
+                "{elapsed_seconds},{},{},{},{},{},{},{}",
+                csv_field(name),
+                position.x,
+                position.y,
+                velocity.x,
+                velocity.y,
+                acceleration.x,
+                acceleration.y
+            );
+        }
+        csv
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+}
+
+/// `text` as a CSV field: quoted, with its quotes doubled, if it has a comma, quote or newline.
+pub fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\"")).into()
+    } else {
+        text.into()
+    }
+}
@@ -0,0 +1,108 @@
+use emath::Vec2;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A 2D vector in `f64`, for simulation state. At solar-system distances `f32` only resolves
+/// positions to hundreds of kilometers, so physics uses this and rendering converts to
+/// [`Vec2`].
+///
+/// Serializes like [`Vec2`], as `(x: …, y: …)`, so state saved when positions were `f32` still
+/// loads.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct DVec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+pub const fn dvec2(x: f64, y: f64) -> DVec2 {
+    DVec2 { x, y }
+}
+
+impl DVec2 {
+    pub const ZERO: Self = dvec2(0., 0.);
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn length_sq(self) -> f64 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product, positive when `other` is counter-clockwise of this.
+    pub fn cross(self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Turned counter-clockwise by `radians`.
+    pub fn rotated(self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        dvec2(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Rounded to `f32` for drawing.
+    pub fn to_vec2(self) -> Vec2 {
+        Vec2::new(self.x as f32, self.y as f32)
+    }
+}
+
+impl From<Vec2> for DVec2 {
+    fn from(v: Vec2) -> Self {
+        dvec2(v.x as f64, v.y as f64)
+    }
+}
+
+impl Add for DVec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        dvec2(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for DVec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for DVec2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        dvec2(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for DVec2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for DVec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        dvec2(-self.x, -self.y)
+    }
+}
+
+impl Mul<f64> for DVec2 {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        dvec2(self.x * factor, self.y * factor)
+    }
+}
+
+impl Div<f64> for DVec2 {
+    type Output = Self;
+
+    fn div(self, divisor: f64) -> Self {
+        dvec2(self.x / divisor, self.y / divisor)
+    }
+}
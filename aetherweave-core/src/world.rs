@@ -0,0 +1,66 @@
+//! The bodies of a simulation, kept by value in one place and looked up by their ids.
+
+use crate::simulation::{Body, BodyId};
+
+/// Every body in a simulation, in order. Bodies are plain data, owned here and nowhere else:
+/// anything that needs one later keeps its [`BodyId`] and looks it up again.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(transparent)]
+pub struct World {
+    bodies: Vec<Body>,
+}
+
+impl World {
+    pub fn new(bodies: Vec<Body>) -> Self {
+        Self { bodies }
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    /// The bodies, to change in place. They can't be added, removed or reordered this way.
+    pub fn bodies_mut(&mut self) -> &mut [Body] {
+        &mut self.bodies
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    pub fn get(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.iter().find(|body| body.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: BodyId) -> Option<&mut Body> {
+        self.bodies.iter_mut().find(|body| body.id == id)
+    }
+
+    pub fn index_of(&self, id: BodyId) -> Option<usize> {
+        self.bodies.iter().position(|body| body.id == id)
+    }
+
+    /// Adds `body` after the others.
+    pub fn push(&mut self, body: Body) {
+        self.bodies.push(body);
+    }
+
+    /// Takes the body with `id` out, keeping the others in order.
+    pub fn remove(&mut self, id: BodyId) -> Option<Body> {
+        Some(self.bodies.remove(self.index_of(id)?))
+    }
+
+    /// Takes the body at `index` out, keeping the others in order.
+    pub(crate) fn remove_at(&mut self, index: usize) -> Body {
+        self.bodies.remove(index)
+    }
+
+    /// Puts `body` in place of the one at `index`, returning that one.
+    pub(crate) fn replace_at(&mut self, index: usize, body: Body) -> Body {
+        std::mem::replace(&mut self.bodies[index], body)
+    }
+}
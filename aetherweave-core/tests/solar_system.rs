@@ -0,0 +1,36 @@
+//! The default system, built and run through the crate's public API.
+
+use aetherweave_core::{scenario, Integrator, Scenario, Simulation};
+
+const YEAR_S: f64 = 365.25 * 86_400.;
+const AU_M: f64 = 1.495_978_707e11;
+
+#[test]
+fn solar_system_holds_together_for_a_year() {
+    let mut simulation = Simulation::new(scenario(Scenario::default()));
+    let count = simulation.bodies().len();
+    let energy = simulation.energy().total_j();
+    let earth_distance = |simulation: &Simulation| {
+        let body = |name: &str| {
+            simulation
+                .bodies()
+                .iter()
+                .find(|body| body.name == name)
+                .map(|body| body.position)
+                .unwrap()
+        };
+        (body("Earth") - body("Sun")).length()
+    };
+    let start = earth_distance(&simulation);
+
+    simulation.run_for(YEAR_S, Integrator::default());
+
+    assert_eq!(simulation.bodies().len(), count, "bodies merged");
+    let drift = (simulation.energy().total_j() - energy) / energy.abs();
+    assert!(drift.abs() < 1e-4, "energy drifted by {drift}");
+    let end = earth_distance(&simulation);
+    assert!(
+        (end - start).abs() < 0.05 * AU_M,
+        "Earth went from {start} m to {end} m from the Sun"
+    );
+}
@@ -1,201 +1,5417 @@
+use aetherweave_core::{
+    approach::{ApproachOutcome, ApproachSearch},
+    calendar::{DateTime, J2000, SECONDS_PER_DAY},
+    cr3bp::Cr3bp,
+    events::{Alignment, AlignmentEvent, AlignmentWatcher},
+    frame::{Frame, FrameTransform},
+    history::History,
+    horizons::{horizons_epoch, horizons_targets, load_horizons, HorizonsTarget},
+    integrator::Integrator,
+    scenario::{
+        generate_belt, scenario, solar_system_on, Scenario, BELT_INNER_KM, BELT_OUTER_KM,
+        DEFAULT_BELT_SEED,
+    },
+    simulation::{
+        corotating_velocity, lagrange_points, roche_limit, Body, BodyId, Collisions, Merger,
+        OrbitalElements, Simulation, EARTH_MASS_KG, SUN_MASS_KG,
+    },
+    spacecraft::{Maneuver, Spacecraft},
+    system_file::{SystemFile, SystemFileError, SystemView},
+    tle::{parse_tles, Propagation},
+    trajectory::csv_field,
+    vector::{dvec2, DVec2},
+};
 use egui::{
-    vec2, Align2, Color32, Event, FontId, Grid, Id, Margin, PointerButton, Pos2, Rect, RichText,
-    Rounding, Stroke, Theme, Ui, Vec2, Window,
+    vec2, Align2, Color32, ColorImage, Event, FontId, Grid, Id, Key, Margin, Modifiers,
+    PointerButton, Pos2, Rect, Response, RichText, Rounding, Sense, Stroke, Theme, Ui, Vec2,
+    Window,
+};
+use egui_plot::{
+    Arrows, GridInput, GridMark, Legend, Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints,
+    PlotResponse, PlotTransform, PlotUi, Points, Text,
 };
-use egui_plot::{Line, LineStyle, Plot, PlotPoint, PlotPoints, Points};
-use std::rc::{Rc, Weak};
+use std::collections::VecDeque;
+use web_time::{Duration, Instant};
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct App {
+    /// `None` until the first frame frames all the bodies.
+    view: Option<View>,
+    #[serde(skip)]
+    view_velocity: ViewVelocity,
+    projection: Projection,
+    /// The reference frame bodies are drawn in.
+    frame: Frame,
+    inertia: bool,
+    body_size: BodySize,
+    /// How many times larger than life bodies are drawn to scale.
+    size_exaggeration: f32,
+    /// Keep the view centered on the selected body.
+    follow: bool,
+    /// Name every body beside its marker. Selected and hovered bodies are named regardless.
+    show_labels: bool,
+    /// Draw a dotted circle through each body around its primary.
+    show_reference_circles: bool,
+    /// Draw grid lines at round distances, labeled along the edges.
+    show_grid: bool,
+    /// Place new bodies at the nearest grid intersection.
+    snap_to_grid: bool,
+    /// How masses, distances and speeds are shown.
+    units: UnitSystem,
+    theme: Theme,
+    /// Mark the system's center of mass with a crosshair.
+    show_barycenter: bool,
+    /// Mark the Lagrange points of the selected body's orbit around its primary.
+    show_lagrange_points: bool,
+    /// The restricted three-body problem being run, if any, in place of the full N-body physics.
+    cr3bp: Option<Cr3bp>,
+    show_cr3bp: bool,
+    show_zero_velocity_curves: bool,
+    /// The Jacobi constant of the zero-velocity curves drawn, in the problem's normalized units.
+    jacobi_constant: f64,
+    /// The primary and secondary picked for a restricted three-body problem.
+    #[serde(skip)]
+    cr3bp_primaries: [Option<BodyId>; 2],
+    /// Show an inset of the whole system in the top right corner, with the view outlined on it.
+    show_minimap: bool,
+    /// Keep the view centered on the barycenter, unless following the selected body.
+    follow_barycenter: bool,
+    velocity_arrows: VelocityArrows,
+    color_by: ColorBy,
+    /// Physics steps of the selected body's predicted path to draw, or zero for none.
+    prediction_steps: usize,
+    #[serde(skip)]
+    prediction: Option<Prediction>,
+    show_diagnostics: bool,
+    show_chart: bool,
+    show_events: bool,
+    /// The alignments listed in the events window, logged when they come about.
+    alignments: AlignmentWatcher,
+    /// Pause when an alignment comes about.
+    pause_on_events: bool,
+    /// Julian dates and descriptions of the alignments that have come about, oldest first.
+    #[serde(skip)]
+    event_log: Vec<(f64, String)>,
+    /// The observer, target and reference of the alignment being picked in the events window.
+    #[serde(skip)]
+    new_alignment: [Option<BodyId>; 3],
+    chart_quantity: ChartQuantity,
+    /// Last path typed into the file prompt for the chart's series.
+    chart_path: String,
+    #[serde(skip)]
+    chart: Chart,
+    /// Show how far the conserved quantities have drifted beside the time controls, keeping
+    /// track of them even while the diagnostics are closed.
+    show_drift: bool,
+    /// The conserved quantities at each frame since they started being tracked.
+    #[serde(skip)]
+    conservation_history: VecDeque<Conserved>,
+    /// How much of each trail, from its oldest end, fades out towards transparent.
+    trail_fade: f32,
+    /// Screen points of arrow per km/s of speed.
+    arrow_scale: f32,
+    /// Falls back to the default solar system when loading state saved without one.
+    simulation: Simulation,
+    integrator: Integrator,
+    /// The scenario last chosen, which [`Self::simulation`] started from.
+    scenario: Scenario,
+    /// Settings for the next asteroid belt added to the simulation.
+    belt_seed: u64,
+    belt_count: usize,
+    /// Make the asteroids of the next belt pull on each other and everything else, rather than
+    /// being test particles.
+    belt_gravitates: bool,
+    paused: bool,
+    /// Run time backwards.
+    reversed: bool,
+    /// Stop the clock while the window or tab doesn't have focus, so that the simulation doesn't
+    /// jump ahead on return.
+    pause_when_unfocused: bool,
+    /// Julian date at which the simulation starts.
+    epoch: f64,
+    /// Simulated seconds per real second.
+    time_scale: f32,
+    /// `egui` time of the previous frame, in seconds.
+    #[serde(skip)]
+    last_update: Option<f64>,
+    /// Bodies picked by clicking or by dragging a box with the secondary button. Most tools act
+    /// on the selection only while it holds a single body.
+    selection: Vec<BodyId>,
+    /// Corners of the selection box being dragged, in screen points.
+    #[serde(skip)]
+    selection_box: Option<[Pos2; 2]>,
+    #[serde(skip)]
+    history: History,
+    #[serde(skip)]
+    placement: Option<BodyPlacement>,
+    #[serde(skip)]
+    elements_dialog: Option<ElementsDialog>,
+    /// The date being picked in the "Go to date" dialog, while it is open.
+    #[serde(skip)]
+    date_dialog: Option<DateTime>,
+    /// The burn being planned, and the spacecraft it is for.
+    #[serde(skip)]
+    maneuver_draft: Option<(BodyId, Maneuver)>,
+    #[serde(skip)]
+    approach_dialog: Option<ApproachDialog>,
+    /// Elapsed simulated seconds being run to, a frame's physics budget at a time, for "Go to
+    /// date".
+    #[serde(skip)]
+    seek_to: Option<f64>,
+    /// Clicking bodies picks them for [`Self::measurement`] instead of selecting them.
+    #[serde(skip)]
+    measuring: bool,
+    /// The two bodies whose distance is shown, as they are picked.
+    #[serde(skip)]
+    measurement: [Option<BodyId>; 2],
+    /// Last path typed into the file prompt.
+    system_path: String,
+    /// Last path typed into the file prompt for a JPL Horizons table.
+    horizons_path: String,
+    /// Last path typed into the file prompt for a trajectory.
+    trajectory_path: String,
+    /// Physics steps between samples of a newly recorded trajectory.
+    trajectory_interval_steps: usize,
+    /// Discard a trajectory's samples once they are exported.
+    clear_trajectory_on_export: bool,
+    /// Last path typed into the file prompt for an image.
+    image_path: String,
+    /// Exported images have this many times the window's resolution along each side.
+    image_scale: u32,
+    #[serde(skip)]
+    image_export: Option<ImageExport>,
+    animation: AnimationSettings,
+    #[serde(skip)]
+    animation_dialog: bool,
+    #[serde(skip)]
+    recording: Option<AnimationRecording>,
+    #[serde(skip)]
+    file_prompt: Option<FileAction>,
+    /// The text in the "Paste Horizons vectors" dialog, while it is open.
+    #[serde(skip)]
+    horizons_paste: Option<String>,
+    #[serde(skip)]
+    tle_dialog: Option<TleDialog>,
+    /// How many simulated seconds the "Export trajectories" dialog will run for, while it is
+    /// open.
+    #[serde(skip)]
+    trajectory_export: Option<f64>,
+    /// Shown until dismissed.
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+/// A cached [`App::predict_path`], valid while the state it was predicted from is unchanged.
+struct Prediction {
+    key: PredictionKey,
+    path: Vec<DVec2>,
+    /// Where along the path the planned burns are, as indices into it.
+    nodes: Vec<usize>,
+    /// Where the burn being planned is, past which the path is the one it would lead to.
+    draft: Option<usize>,
+}
+
+/// A PNG of the plot at `scale` times the window's resolution, assembled from `scale²`
+/// screenshots taken with the view zoomed in on each tile in turn.
+struct ImageExport {
+    path: String,
+    scale: u32,
+    /// The view being tiled, restored once the export is done.
+    view: View,
+    /// Index of the tile on screen, row by row from the top left.
+    tile: u32,
+    /// Whether a screenshot of [`Self::tile`] is on its way.
+    requested: bool,
+    /// Where the plot was last drawn, in points.
+    rect: Rect,
+    /// Size of one tile, in pixels.
+    tile_size: [usize; 2],
+    image: ColorImage,
+}
+
+impl ImageExport {
+    fn new(path: String, scale: u32, view: View) -> Self {
+        Self {
+            path,
+            scale,
+            view,
+            tile: 0,
+            requested: false,
+            rect: Rect::NOTHING,
+            tile_size: [0, 0],
+            image: ColorImage::default(),
+        }
+    }
+
+    fn column_and_row(&self) -> [usize; 2] {
+        [self.tile % self.scale, self.tile / self.scale].map(|i| i as usize)
+    }
+
+    /// The part of [`Self::view`] covered by the current tile, filling a plot of `size`.
+    fn tile_view(&self, size: Vec2) -> View {
+        let [column, row] = self.column_and_row();
+        let tiles = self.scale as f32;
+        // From the center of the whole view to the center of the tile, as a fraction of its size.
+        let offset =
+            (vec2(column as f32, row as f32) + Vec2::splat(0.5)) / tiles - Vec2::splat(0.5);
+        View {
+            center: self.view.center + vec2(offset.x, -offset.y) * size * self.view.scale,
+            scale: self.view.scale / tiles,
+        }
+    }
+
+    /// Copies the plot out of a `screenshot` taken while `tile` was on screen, and moves on to
+    /// the next tile. Returns whether that was the last one.
+    fn receive(&mut self, tile: u32, screenshot: &ColorImage, pixels_per_point: f32) -> bool {
+        if tile != self.tile {
+            return false;
+        }
+        let part = screenshot.region(&self.rect, Some(pixels_per_point));
+        if self.tile == 0 {
+            self.tile_size = part.size;
+            let tiles = self.scale as usize;
+            self.image = ColorImage::new(
+                [part.size[0] * tiles, part.size[1] * tiles],
+                Color32::TRANSPARENT,
+            );
+        }
+        // Should the window be resized partway through, later tiles are cropped to fit.
+        let [width, height] = [0, 1].map(|i| part.size[i].min(self.tile_size[i]));
+        let [column, row] = self.column_and_row();
+        for y in 0..height {
+            let start =
+                (row * self.tile_size[1] + y) * self.image.size[0] + column * self.tile_size[0];
+            self.image.pixels[start..start + width]
+                .copy_from_slice(&part.pixels[y * part.size[0]..][..width]);
+        }
+        self.tile += 1;
+        self.requested = false;
+        self.tile == self.scale * self.scale
+    }
+
+    fn save(&self) -> Result<(), png::EncodingError> {
+        save_png(&self.path, &self.image)
+    }
+}
+
+fn png_encoder(
+    path: &str,
+    size: [usize; 2],
+) -> Result<png::Encoder<'static, std::io::BufWriter<std::fs::File>>, png::EncodingError> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let [width, height] = size.map(|size| size as u32);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    Ok(encoder)
+}
+
+fn save_png(path: &str, image: &ColorImage) -> Result<(), png::EncodingError> {
+    png_encoder(path, image.size)?
+        .write_header()?
+        .write_image_data(image.as_raw())
+}
+
+/// How an animation is written.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+enum AnimationFormat {
+    /// One PNG per frame, numbered as by [`numbered_path`].
+    PngSequence,
+    /// A single animated PNG, which browsers and presentation software play.
+    Apng,
+}
+
+impl AnimationFormat {
+    const ALL: [Self; 2] = [Self::PngSequence, Self::Apng];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::PngSequence => "Numbered PNGs",
+            Self::Apng => "Animated PNG",
+        }
+    }
+}
+
+/// The "Record animation" dialog's settings, kept between recordings.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+struct AnimationSettings {
+    path: String,
+    format: AnimationFormat,
+    /// Simulated time between frames.
+    interval_s: f64,
+    frames: usize,
+    /// Playback speed of an APNG.
+    frames_per_second: u16,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            format: AnimationFormat::Apng,
+            interval_s: DAY_S as f64,
+            frames: 365,
+            frames_per_second: 30,
+        }
+    }
+}
+
+/// `path` with `frame` numbered in before its extension, as `orbit_0001.png` for `orbit.png`.
+fn numbered_path(path: &str, frame: usize) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, extension)) if !extension.contains(['/', '\\']) => {
+            format!("{stem}_{frame:04}.{extension}")
+        }
+        _ => format!("{path}_{frame:04}.png"),
+    }
+}
+
+/// Identifies a screenshot taken for an [`AnimationRecording`], by its frame number.
+struct AnimationFrame(usize);
+
+/// The plot captured every [`AnimationSettings::interval_s`] of simulated time, running the
+/// simulation exactly that long between screenshots however long each takes to arrive.
+struct AnimationRecording {
+    settings: AnimationSettings,
+    /// Frames captured so far.
+    captured: usize,
+    /// Whether a screenshot of the next frame is on its way.
+    requested: bool,
+    /// Where the plot was last drawn, in points.
+    rect: Rect,
+    /// The frames of an APNG, held until they are all in.
+    frames: Vec<ColorImage>,
+}
+
+impl AnimationRecording {
+    fn new(settings: AnimationSettings) -> Self {
+        Self {
+            settings,
+            captured: 0,
+            requested: false,
+            rect: Rect::NOTHING,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Copies the plot out of the `screenshot` of `frame`, writing it straight away in a PNG
+    /// sequence. Returns whether it was the last frame.
+    fn receive(
+        &mut self,
+        frame: usize,
+        screenshot: &ColorImage,
+        pixels_per_point: f32,
+    ) -> Result<bool, png::EncodingError> {
+        if frame != self.captured {
+            return Ok(false);
+        }
+        let image = screenshot.region(&self.rect, Some(pixels_per_point));
+        match self.settings.format {
+            AnimationFormat::PngSequence => {
+                save_png(&numbered_path(&self.settings.path, frame + 1), &image)?
+            }
+            AnimationFormat::Apng => self.frames.push(image),
+        }
+        self.captured += 1;
+        self.requested = false;
+        Ok(self.captured == self.settings.frames)
+    }
+
+    /// Writes the frames of an APNG, all cropped or padded to the size of the first should the
+    /// window have been resized along the way.
+    fn finish(&self) -> Result<(), png::EncodingError> {
+        let Some(first) = self.frames.first() else {
+            return Ok(());
+        };
+        let size = first.size;
+        let mut encoder = png_encoder(&self.settings.path, size)?;
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+        encoder.set_frame_delay(1, self.settings.frames_per_second.max(1))?;
+        let mut writer = encoder.write_header()?;
+        for frame in &self.frames {
+            let mut fitted = ColorImage::new(size, Color32::TRANSPARENT);
+            let [width, height] = [0, 1].map(|i| frame.size[i].min(size[i]));
+            for y in 0..height {
+                fitted.pixels[y * size[0]..][..width]
+                    .copy_from_slice(&frame.pixels[y * frame.size[0]..][..width]);
+            }
+            writer.write_image_data(fitted.as_raw())?;
+        }
+        writer.finish()
+    }
+}
+
+#[derive(PartialEq)]
+struct PredictionKey {
+    body: BodyId,
+    mass_kg: f64,
+    position: DVec2,
+    velocity: DVec2,
+    elapsed_seconds: f64,
+    body_count: usize,
+    steps: usize,
+    timestep_s: f64,
+    integrator: Integrator,
+    frame: Frame,
+    maneuvers: Vec<Maneuver>,
+    draft: Option<Maneuver>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+enum UnitSystem {
+    /// Kilograms, kilometers and km/s.
+    #[default]
+    Si,
+    /// Solar masses, AU and AU per day.
+    Astronomical,
+}
+
+impl UnitSystem {
+    const ALL: [Self; 2] = [Self::Si, Self::Astronomical];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Si => "SI (kg, km)",
+            Self::Astronomical => "Astronomical (M☉, AU, days)",
+        }
+    }
+
+    /// Kilograms per displayed unit of mass, and its symbol.
+    fn mass_unit(self) -> (f64, &'static str) {
+        match self {
+            Self::Si => (1., "kg"),
+            Self::Astronomical => (SUN_MASS_KG, "M☉"),
+        }
+    }
+
+    /// Meters per displayed unit of distance, and its symbol.
+    fn distance_unit(self) -> (f64, &'static str) {
+        match self {
+            Self::Si => (1e3, "km"),
+            Self::Astronomical => (AU_M, "AU"),
+        }
+    }
+
+    /// m/s per displayed unit of speed, and its symbol.
+    fn speed_unit(self) -> (f64, &'static str) {
+        match self {
+            Self::Si => (1e3, "km/s"),
+            Self::Astronomical => (AU_M / DAY_S as f64, "AU/d"),
+        }
+    }
+
+    fn distance(self, meters: f64) -> String {
+        let (per_unit, symbol) = self.distance_unit();
+        format!("{} {symbol}", format_number(meters / per_unit))
+    }
+
+    fn mass(self, kg: f64) -> String {
+        let (per_unit, symbol) = self.mass_unit();
+        format!("{} {symbol}", format_number(kg / per_unit))
+    }
+
+    fn speed(self, meters_per_second: f64) -> String {
+        let (per_unit, symbol) = self.speed_unit();
+        format!("{} {symbol}", format_number(meters_per_second / per_unit))
+    }
+}
+
+/// The state of the closest-approach dialog.
+struct ApproachDialog {
+    bodies: [Option<BodyId>; 2],
+    horizon_s: f64,
+    search: Option<ApproachSearch>,
+    /// The outcome of the last search, and the bodies it was for.
+    outcome: Option<([BodyId; 2], ApproachOutcome)>,
+}
+
+/// Which bodies get an arrow along their velocity.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+enum VelocityArrows {
+    #[default]
+    Off,
+    Selected,
+    All,
+}
+
+impl VelocityArrows {
+    const ALL: [Self; 3] = [Self::Off, Self::Selected, Self::All];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Selected => "Selected body",
+            Self::All => "All bodies",
+        }
+    }
+}
+
+/// How large each body's marker is drawn.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+enum BodySize {
+    /// All the same size.
+    #[default]
+    Fixed,
+    /// Growing with the log of the body's physical radius.
+    Logarithmic,
+    /// As large as the body is, in the plot's scale, and never smaller than a dot. Only true to
+    /// scale with linear distances.
+    ToScale,
+}
+
+impl BodySize {
+    const ALL: [Self; 3] = [Self::Fixed, Self::Logarithmic, Self::ToScale];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Fixed => "Fixed",
+            Self::Logarithmic => "By radius",
+            Self::ToScale => "To scale",
+        }
+    }
+}
+
+/// What [`marker_radius`] needs to size a body's marker.
+#[derive(Clone, Copy)]
+struct MarkerSizing {
+    size: BodySize,
+    exaggeration: f32,
+    /// Plot units per screen point.
+    scale: f32,
+}
+
+/// Colors for what's drawn over the plot, legible against the background of the theme.
+struct Palette {
+    text: Color32,
+    /// The selected body and the barycenter.
+    highlight: Color32,
+    measurement: Color32,
+    lagrange: Color32,
+}
+
+impl Palette {
+    fn new(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => Self {
+                text: Color32::LIGHT_GRAY,
+                highlight: Color32::WHITE,
+                measurement: Color32::LIGHT_YELLOW,
+                lagrange: Color32::LIGHT_BLUE,
+            },
+            Theme::Light => Self {
+                text: Color32::DARK_GRAY,
+                highlight: Color32::BLACK,
+                measurement: Color32::from_rgb(150, 110, 0),
+                lagrange: Color32::DARK_BLUE,
+            },
+        }
+    }
+}
+
+/// What bodies are colored by.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+enum ColorBy {
+    /// Each body's own [`Body::color`].
+    #[default]
+    Body,
+    Speed,
+    /// On a log scale, since masses span many orders of magnitude.
+    Mass,
+    /// From the heaviest body.
+    Distance,
+}
+
+impl ColorBy {
+    const ALL: [Self; 4] = [Self::Body, Self::Speed, Self::Mass, Self::Distance];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Body => "Body color",
+            Self::Speed => "Speed",
+            Self::Mass => "Mass",
+            Self::Distance => "Distance",
+        }
+    }
+
+    /// The property of `body` being shown, in SI units, or `None` for [`Self::Body`].
+    fn value(self, body: &Body, heaviest: &Body) -> Option<f64> {
+        match self {
+            Self::Body => None,
+            Self::Speed => Some(body.velocity.length()),
+            Self::Mass => Some(body.mass_kg),
+            Self::Distance => Some((body.position - heaviest.position).length()),
+        }
+    }
+
+    /// Where `value` falls between `min` and `max`, from zero to one.
+    fn normalize(self, value: f64, [min, max]: [f64; 2]) -> f32 {
+        let scale = |value: f64| match self {
+            Self::Mass => value.max(f64::MIN_POSITIVE).log10(),
+            _ => value,
+        };
+        let range = scale(max) - scale(min);
+        if range > 0. {
+            ((scale(value) - scale(min)) / range) as f32
+        } else {
+            0.5
+        }
+    }
+
+    fn format(self, value: f64, units: UnitSystem) -> String {
+        match self {
+            Self::Body => String::new(),
+            Self::Speed => units.speed(value),
+            Self::Mass => units.mass(value),
+            Self::Distance => units.distance(value),
+        }
+    }
+}
+
+/// What the path typed into the file prompt is for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FileAction {
+    OpenSystem,
+    SaveSystem,
+    ImportHorizons,
+    ExportTrajectory,
+    ExportChart,
+    ExportImage,
+}
+
+impl FileAction {
+    fn title(self) -> &'static str {
+        match self {
+            Self::OpenSystem => "Open system",
+            Self::SaveSystem => "Save system",
+            Self::ImportHorizons => "Import Horizons vectors",
+            Self::ExportTrajectory => "Export trajectory",
+            Self::ExportChart => "Export chart",
+            Self::ExportImage => "Export image",
+        }
+    }
+
+    fn format(self) -> &'static str {
+        match self {
+            Self::OpenSystem | Self::SaveSystem => "RON",
+            Self::ImportHorizons => "Horizons vector table CSV",
+            Self::ExportTrajectory | Self::ExportChart => "CSV",
+            Self::ExportImage => "PNG",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+struct View {
+    center: Vec2,
+    /// Meters per screen point.
+    scale: f32,
+}
+
+/// How simulated positions are mapped onto the plot. Only rendering goes through this: physics
+/// and readouts always use true positions.
+///
+/// Bodies' inclinations give a pseudo-3D look. A body's position is rotated out of the plane
+/// about the x axis by its inclination, the whole system is then tipped away from the viewer
+/// about the same axis by `tilt_deg`, and the result is projected orthographically onto the
+/// screen. This is only a visual approximation: the bodies still move and interact in the plane.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+struct Projection {
+    /// Draw distances from the origin on a `log10` scale, preserving angles, so that inner and
+    /// outer planets are legible together.
+    log_distances: bool,
+    /// How far the system is viewed from directly above its plane, in degrees.
+    tilt_deg: f32,
+    /// Draw bodies between their last two states; see [`Simulation::step_lag`].
+    #[serde(skip)]
+    step_lag: f64,
+    /// Into the reference frame as it is now.
+    #[serde(skip)]
+    frame: FrameTransform,
+}
+
+impl Projection {
+    /// Distance that maps to one decade on the logarithmic scale, in meters.
+    const LOG_REFERENCE: f32 = 1e9;
+    /// Plot length of one decade, chosen to keep the plot about as large as the linear one.
+    const LOG_DECADE: f32 = 1e12;
+
+    fn distance(&self, distance: f32) -> f32 {
+        if self.log_distances {
+            (1. + distance / Self::LOG_REFERENCE).log10() * Self::LOG_DECADE
+        } else {
+            distance
+        }
+    }
+
+    /// Keeps the plane from being seen edge-on, where it can't be inverted.
+    const MAX_TILT_DEG: f32 = 80.;
+
+    /// Where a point in the plane of the system is drawn.
+    fn apply(&self, position: DVec2) -> Vec2 {
+        self.apply_inclined(position, 0.)
+    }
+
+    /// Where a point at `position`, in a plane inclined by `inclination_deg`, is drawn.
+    fn apply_inclined(&self, position: DVec2, inclination_deg: f32) -> Vec2 {
+        self.project(self.frame.apply(position), inclination_deg)
+    }
+
+    /// Like [`Self::apply_inclined`], for a `position` already in the reference frame.
+    fn project(&self, position: DVec2, inclination_deg: f32) -> Vec2 {
+        let distance = position.length();
+        let position = if !self.log_distances || distance == 0. {
+            position.to_vec2()
+        } else {
+            (position * (self.distance(distance as f32) as f64 / distance)).to_vec2()
+        };
+        // Out of the plane is towards the viewer.
+        let (out_of_plane, in_plane) = inclination_deg.to_radians().sin_cos();
+        let (sin_tilt, cos_tilt) = self.tilt_deg.to_radians().sin_cos();
+        let [y, z] = [in_plane, out_of_plane].map(|component| position.y * component);
+        vec2(position.x, y * cos_tilt + z * sin_tilt)
+    }
+
+    /// Where `body` is drawn.
+    fn body(&self, body: &Body) -> Vec2 {
+        self.apply_inclined(body.drawn_position(self.step_lag), body.inclination_deg)
+    }
+
+    /// Where `position` is drawn on screen.
+    fn screen_position(&self, transform: &PlotTransform, position: DVec2) -> Pos2 {
+        screen_position(transform, self.apply(position))
+    }
+
+    /// Where `body` is drawn on screen.
+    fn body_screen_position(&self, transform: &PlotTransform, body: &Body) -> Pos2 {
+        screen_position(transform, self.body(body))
+    }
+
+    /// The point in the plane of the system drawn at `plot_position`, in the reference frame.
+    fn unproject(&self, plot_position: Vec2) -> DVec2 {
+        let plot_position = vec2(
+            plot_position.x,
+            plot_position.y / self.tilt_deg.to_radians().cos(),
+        );
+        let plot_position = DVec2::from(plot_position);
+        let plot_distance = plot_position.length();
+        if !self.log_distances || plot_distance == 0. {
+            return plot_position;
+        }
+        let decades = plot_distance / Self::LOG_DECADE as f64;
+        let distance = Self::LOG_REFERENCE as f64 * (10f64.powf(decades) - 1.);
+        plot_position * (distance / plot_distance)
+    }
+}
+
+fn screen_position(transform: &PlotTransform, plot_position: Vec2) -> Pos2 {
+    transform.position_from_point(&PlotPoint::new(
+        plot_position.x as f64,
+        plot_position.y as f64,
+    ))
+}
+
+/// "Add body" mode: the new body's settings, and its placement while the user drags out its
+/// velocity.
+struct BodyPlacement {
+    mass_earths: f64,
+    color: Color32,
+    /// Where the drag started, in meters in the reference frame.
+    start: Option<DVec2>,
+    /// The drag so far, in screen points.
+    drag: Vec2,
+}
+
+impl Default for BodyPlacement {
+    fn default() -> Self {
+        Self {
+            mass_earths: 1.,
+            color: Color32::WHITE,
+            start: None,
+            drag: Vec2::ZERO,
+        }
+    }
+}
+
+impl BodyPlacement {
+    /// Launch speed per screen point of drag, in m/s.
+    const SPEED_PER_POINT: f64 = 300.;
+
+    fn velocity(screen_delta: Vec2) -> DVec2 {
+        DVec2::from(vec2(screen_delta.x, -screen_delta.y)) * Self::SPEED_PER_POINT
+    }
+}
+
+/// The "New body from elements" dialog's settings, for a body on a given orbit around a chosen
+/// primary.
+struct ElementsDialog {
+    name: String,
+    mass_earths: f64,
+    color: Color32,
+    primary: BodyId,
+    elements: OrbitalElements,
+}
+
+impl ElementsDialog {
+    fn new(primary: &Body, name: String) -> Self {
+        Self {
+            name,
+            mass_earths: 1.,
+            color: Color32::WHITE,
+            primary: primary.id,
+            elements: OrbitalElements {
+                semi_major_axis_m: AU_M,
+                eccentricity: 0.,
+                inclination_deg: 0.,
+                argument_of_periapsis_deg: 0.,
+                true_anomaly_deg: 0.,
+                period_s: None,
+                distance_m: AU_M,
+            },
+        }
+    }
+}
+
+/// The "Add satellites from TLEs" dialog's settings.
+struct TleDialog {
+    text: String,
+    propagation: Propagation,
+    /// Switch to [`App::earth_orbit_view`] once the satellites are added.
+    earth_view: bool,
+}
+
+impl Default for TleDialog {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            propagation: Default::default(),
+            earth_view: true,
+        }
+    }
+}
+
+/// Satellites are given these colors in turn.
+const SATELLITE_COLORS: [Color32; 4] = [
+    Color32::LIGHT_GREEN,
+    Color32::LIGHT_BLUE,
+    Color32::YELLOW,
+    Color32::LIGHT_RED,
+];
+/// Element sets don't say how big a satellite is: about the International Space Station's mass,
+/// with a radius to match. They are test particles, so neither affects the orbits.
+const SATELLITE_MASS_KG: f64 = 4e5;
+const SATELLITE_RADIUS_KM: f32 = 0.05;
+/// Test particles put at Lagrange points, to watch whether they stay.
+const LAGRANGE_PARTICLE_MASS_KG: f64 = 1e3;
+const LAGRANGE_PARTICLE_RADIUS_KM: f32 = 0.01;
+const LAGRANGE_PARTICLE_COLOR: Color32 = Color32::LIGHT_BLUE;
+/// Just beyond geostationary orbit, for [`App::earth_orbit_view`].
+const EARTH_ORBIT_VIEW_M: f32 = 4.5e7;
+/// The longest step that keeps a low Earth orbit, about 90 minutes around, from coming apart.
+const LOW_ORBIT_TIMESTEP_S: f64 = 60.;
+
+/// What the chart plots over time.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ChartQuantity {
+    /// Each selected body's distance from the body called Sun.
+    #[default]
+    DistanceFromSun,
+    /// How far apart the first two selected bodies are.
+    Separation,
+    /// Each selected body's speed in the reference frame.
+    Speed,
+}
+
+impl ChartQuantity {
+    const ALL: [Self; 3] = [Self::DistanceFromSun, Self::Separation, Self::Speed];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::DistanceFromSun => "Distance from Sun",
+            Self::Separation => "Distance between two",
+            Self::Speed => "Speed",
+        }
+    }
+
+    /// The CSV column heading for its values.
+    fn column(self) -> &'static str {
+        match self {
+            Self::DistanceFromSun | Self::Separation => "distance_m",
+            Self::Speed => "speed_m_s",
+        }
+    }
+}
+
+/// The series the chart has recorded, one per line, since the quantity or selection they follow
+/// last changed.
+#[derive(Default)]
+struct Chart {
+    quantity: ChartQuantity,
+    selection: Vec<BodyId>,
+    series: Vec<ChartSeries>,
+}
+
+struct ChartSeries {
+    name: String,
+    /// Simulated seconds and the quantity's value, in meters or m/s.
+    points: VecDeque<[f64; 2]>,
+}
+
+impl Chart {
+    /// Points kept in each series, the oldest dropped first.
+    const MAX_POINTS: usize = 10_000;
+
+    /// One row per point of each series, in seconds and meters or m/s.
+    fn to_csv(&self) -> String {
+        let mut csv = format!("time_s,series,{}\n", self.quantity.column());
+        for series in &self.series {
+            let name = csv_field(&series.name);
+            for [elapsed_s, value] in &series.points {
+                csv.push_str(&format!("{elapsed_s},{name},{value}\n"));
+            }
+        }
+        csv
+    }
+}
+
+/// The quantities an isolated system conserves, at one moment, for tracking how far an integrator
+/// lets them drift.
+#[derive(Clone, Copy, Debug)]
+struct Conserved {
+    elapsed_s: f64,
+    energy_j: f64,
+    /// In kg m/s.
+    momentum: DVec2,
+    /// About the origin, in kg m²/s.
+    angular_momentum: f64,
+    /// Sums of the bodies' own momenta and angular momenta, to measure the totals' drift
+    /// against: either total can be near zero, as when the barycenter is at rest.
+    momentum_scale: f64,
+    angular_momentum_scale: f64,
+}
+
+impl Conserved {
+    fn of(simulation: &Simulation) -> Self {
+        let [momentum_scale, angular_momentum_scale] =
+            simulation
+                .bodies()
+                .iter()
+                .fold([0.; 2], |[linear, angular], body| {
+                    let (mass_kg, position, velocity) =
+                        (body.mass_kg, body.position, body.velocity);
+                    [
+                        linear + mass_kg * velocity.length(),
+                        angular + mass_kg * position.cross(velocity).abs(),
+                    ]
+                });
+        Self {
+            elapsed_s: simulation.elapsed_seconds(),
+            energy_j: simulation.energy().total_j(),
+            momentum: simulation.momentum(),
+            angular_momentum: simulation.angular_momentum(),
+            momentum_scale,
+            angular_momentum_scale,
+        }
+    }
+
+    /// How far energy, momentum and angular momentum have each drifted since `start`, as
+    /// fractions.
+    fn drift_since(&self, start: &Self) -> [f64; 3] {
+        let relative = |change: f64, scale: f64| if scale > 0. { change / scale } else { 0. };
+        [
+            relative(self.energy_j - start.energy_j, start.energy_j.abs()),
+            relative(
+                (self.momentum - start.momentum).length(),
+                start.momentum_scale,
+            ),
+            relative(
+                self.angular_momentum - start.angular_momentum,
+                start.angular_momentum_scale,
+            ),
+        ]
+    }
+}
+
+/// How fast the view keeps moving after a flick-pan or scroll-zoom is released.
+#[derive(Clone, Copy, Debug, Default)]
+struct ViewVelocity {
+    /// Meters per second.
+    pan: Vec2,
+    /// Natural log of the zoom factor per second.
+    zoom: f32,
+    /// World point that stays fixed on screen while zooming.
+    zoom_anchor: Vec2,
+}
+
+impl View {
+    const MIN_SCALE: f32 = 1e3;
+    const MAX_SCALE: f32 = 1e11;
+    const ZOOM_PER_SCROLL_POINT: f32 = 0.005;
+    /// Exponential decay rate of inertial motion, per second.
+    const INERTIA_DECAY: f32 = 4.;
+    /// Below this speed (screen points per second) an inertial pan stops.
+    const MIN_PAN_SPEED: f32 = 5.;
+    /// Below this rate (log zoom per second) an inertial zoom stops.
+    const MIN_ZOOM_SPEED: f32 = 0.01;
+    /// About how far apart grid lines are, in screen points.
+    const GRID_SPACING: f32 = 80.;
+
+    /// Frames all `bodies` in a plot of the given screen `size`.
+    fn fit(bodies: &[Body], projection: Projection, size: Vec2) -> Self {
+        let extent = bodies
+            .iter()
+            .map(|body| projection.body(body).length())
+            .fold(0., f32::max);
+        Self {
+            center: Vec2::ZERO,
+            scale: (2.2 * extent / size.min_elem()).clamp(Self::MIN_SCALE, Self::MAX_SCALE),
+        }
+    }
+
+    /// Spacing of grid lines, in meters: a round distance in `units`, so that lines are about
+    /// [`Self::GRID_SPACING`] apart on screen.
+    fn grid_step(&self, units: UnitSystem) -> f64 {
+        let (meters_per_unit, _) = units.distance_unit();
+        round_length((Self::GRID_SPACING * self.scale) as f64 / meters_per_unit) * meters_per_unit
+    }
+
+    fn bounds(&self, size: Vec2) -> PlotBounds {
+        let min = self.center - size * 0.5 * self.scale;
+        let max = self.center + size * 0.5 * self.scale;
+        PlotBounds::from_min_max([min.x as f64, min.y as f64], [max.x as f64, max.y as f64])
+    }
+
+    fn world_from_screen(&self, pos: Pos2, rect: Rect) -> Vec2 {
+        let offset = pos - rect.center();
+        self.center + vec2(offset.x, -offset.y) * self.scale
+    }
+
+    /// Moves the view by a screen-space drag, returning the change of center in meters.
+    fn pan(&mut self, screen_delta: Vec2) -> Vec2 {
+        let delta = vec2(-screen_delta.x, screen_delta.y) * self.scale;
+        self.center += delta;
+        delta
+    }
+
+    /// Zooms in by `factor` around `anchor`, returning `false` if the scale clamp was hit.
+    fn zoom(&mut self, factor: f32, anchor: Vec2) -> bool {
+        let scale = (self.scale / factor).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        self.center = anchor + (self.center - anchor) * (scale / self.scale);
+        let clamped = scale != self.scale / factor;
+        self.scale = scale;
+        !clamped
+    }
+
+    /// Applies this frame's drag and scroll input on the plot, or coasts on `velocity` once the
+    /// input has ended and `inertia` is enabled. Dragging with the middle button always pans;
+    /// dragging with the primary one only does if `allow_pan`, since it may be busy otherwise.
+    fn navigate(
+        &mut self,
+        response: &Response,
+        velocity: &mut ViewVelocity,
+        inertia: bool,
+        allow_pan: bool,
+    ) {
+        let (dt, scroll, zoom_delta, pointer) = response.ctx.input(|i| {
+            (
+                i.stable_dt.max(f32::EPSILON),
+                i.smooth_scroll_delta.y,
+                i.zoom_delta(),
+                i.pointer.hover_pos(),
+            )
+        });
+        let panning = (allow_pan && response.dragged_by(PointerButton::Primary))
+            || response.dragged_by(PointerButton::Middle);
+        if panning {
+            let delta = self.pan(response.drag_delta());
+            velocity.pan = (velocity.pan + delta / dt) * 0.5;
+        }
+        let factor = zoom_delta * (scroll * Self::ZOOM_PER_SCROLL_POINT).exp();
+        let zooming = response.hovered() && factor != 1.;
+        if zooming {
+            let anchor = pointer.map_or(self.center, |pos| {
+                self.world_from_screen(pos, response.rect)
+            });
+            velocity.zoom = factor.ln() / dt;
+            velocity.zoom_anchor = anchor;
+            if !self.zoom(factor, anchor) {
+                velocity.zoom = 0.;
+            }
+        }
+        if !inertia {
+            *velocity = Default::default();
+            return;
+        }
+        let decay = (-Self::INERTIA_DECAY * dt).exp();
+        if !panning {
+            self.center += velocity.pan * dt;
+            velocity.pan *= decay;
+            if velocity.pan.length() / self.scale < Self::MIN_PAN_SPEED {
+                velocity.pan = Vec2::ZERO;
+            }
+        }
+        if !zooming {
+            let coasting = self.zoom((velocity.zoom * dt).exp(), velocity.zoom_anchor);
+            velocity.zoom *= decay;
+            if !coasting || velocity.zoom.abs() < Self::MIN_ZOOM_SPEED {
+                velocity.zoom = 0.;
+            }
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            simulation: Default::default(),
+            integrator: Default::default(),
+            scenario: Default::default(),
+            belt_seed: DEFAULT_BELT_SEED,
+            belt_count: 200,
+            belt_gravitates: true,
+            paused: false,
+            reversed: false,
+            pause_when_unfocused: true,
+            epoch: J2000,
+            time_scale: Self::DEFAULT_TIME_SCALE,
+            last_update: None,
+            view: None,
+            view_velocity: Default::default(),
+            projection: Default::default(),
+            frame: Default::default(),
+            inertia: true,
+            body_size: Default::default(),
+            size_exaggeration: 1.,
+            follow: false,
+            show_labels: true,
+            show_reference_circles: true,
+            show_grid: false,
+            snap_to_grid: false,
+            units: Default::default(),
+            show_barycenter: false,
+            show_lagrange_points: false,
+            cr3bp: None,
+            show_cr3bp: false,
+            show_zero_velocity_curves: true,
+            jacobi_constant: 3.,
+            cr3bp_primaries: [None; 2],
+            show_minimap: true,
+            follow_barycenter: false,
+            velocity_arrows: Default::default(),
+            color_by: Default::default(),
+            theme: Theme::Dark,
+            prediction_steps: 0,
+            prediction: None,
+            show_diagnostics: false,
+            show_chart: false,
+            show_events: false,
+            alignments: Default::default(),
+            pause_on_events: false,
+            event_log: Vec::new(),
+            new_alignment: Default::default(),
+            chart_quantity: Default::default(),
+            chart_path: String::new(),
+            chart: Default::default(),
+            show_drift: false,
+            conservation_history: Default::default(),
+            arrow_scale: 2.,
+            trail_fade: 1.,
+            selection: Vec::new(),
+            selection_box: None,
+            history: Default::default(),
+            placement: None,
+            elements_dialog: None,
+            date_dialog: None,
+            maneuver_draft: None,
+            approach_dialog: None,
+            seek_to: None,
+            measuring: false,
+            measurement: Default::default(),
+            system_path: String::new(),
+            horizons_path: String::new(),
+            trajectory_path: String::new(),
+            trajectory_interval_steps: 1,
+            clear_trajectory_on_export: true,
+            image_path: String::new(),
+            image_scale: 1,
+            image_export: None,
+            animation: Default::default(),
+            animation_dialog: false,
+            recording: None,
+            file_prompt: None,
+            horizons_paste: None,
+            tle_dialog: None,
+            trajectory_export: None,
+            error: None,
+        }
+    }
+}
+
+impl App {
+    /// About 20 seconds per Earth year.
+    const DEFAULT_TIME_SCALE: f32 = 1.5e6;
+    /// Real time.
+    const MIN_TIME_SCALE: f32 = 1.;
+    /// A thousand years a minute, though a frame only has so many steps to spend, so the
+    /// simulation falls short of this with many bodies or short steps.
+    const MAX_TIME_SCALE: f32 = 1000. * YEAR_S / MINUTE_S;
+    /// Most real time one frame advances the simulation by, in seconds, so that a frame after a
+    /// stall slows the simulation for a moment instead of leaping it ahead.
+    const MAX_FRAME_DT: f64 = 1. / 30.;
+    /// Most wall-clock time physics may take each frame, leaving the rest of a 60 Hz frame for
+    /// drawing. A simulation that needs longer falls behind the time scale instead.
+    const PHYSICS_BUDGET: Duration = Duration::from_millis(10);
+    /// How far past a burn being planned its path is shown when the orbit it leads to has no
+    /// period, in steps, and how far at most.
+    const MANEUVER_PREVIEW_STEPS: usize = 2_000;
+    const MAX_MANEUVER_PREVIEW_STEPS: usize = 20_000;
+    /// Opacity of the newest part of a trail.
+    const TRAIL_OPACITY: f32 = 0.6;
+    /// Steps of opacity a trail fades out in.
+    const TRAIL_FADE_LEVELS: usize = 24;
+    /// Lines drawn for all trails together, at most, besides one for each trail's unfaded part.
+    const MAX_TRAIL_LINES: usize = 2_000;
+    /// Frames of conserved quantities kept for the diagnostics' drift plot.
+    const CONSERVATION_HISTORY_LENGTH: usize = 1000;
+
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        cc.egui_ctx.set_theme(app.theme);
+        app
+    }
+
+    /// Replaces the whole simulation, starting trails and the view afresh.
+    fn load(&mut self, simulation: Simulation) {
+        self.record_history();
+        let trail_duration_s = self.simulation.trail_duration_s;
+        self.simulation = simulation;
+        self.simulation.trail_duration_s = trail_duration_s;
+        self.selection.clear();
+        self.follow = false;
+        self.view = None;
+        self.view_velocity = Default::default();
+        self.conservation_history.clear();
+        self.alignments.reset();
+    }
+
+    fn run_file_action(&mut self, action: FileAction) {
+        let result = match action {
+            FileAction::OpenSystem => self.open_system().map_err(|error| error.to_string()),
+            FileAction::SaveSystem => self.save_system().map_err(|error| error.to_string()),
+            FileAction::ImportHorizons => self.import_horizons(),
+            FileAction::ExportTrajectory => self
+                .export_trajectory()
+                .map_err(|error| format!("Couldn't write the trajectory: {error}")),
+            FileAction::ExportChart => std::fs::write(&self.chart_path, self.chart.to_csv())
+                .map_err(|error| format!("Couldn't write the chart: {error}")),
+            FileAction::ExportImage => {
+                // Finished over the next few frames; see `Self::update`.
+                self.image_export = self
+                    .view
+                    .clone()
+                    .map(|view| ImageExport::new(self.image_path.clone(), self.image_scale, view));
+                Ok(())
+            }
+        };
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    /// Replaces the simulation with the system saved at [`Self::system_path`], picking up its
+    /// start date, time scale and view too if it has them.
+    fn open_system(&mut self) -> Result<(), SystemFileError> {
+        let text = std::fs::read_to_string(&self.system_path).map_err(SystemFileError::Io)?;
+        let file = SystemFile::parse(&text)?;
+        self.load(file.to_simulation()?);
+        if let Some(epoch) = file.epoch {
+            self.epoch = epoch;
+        }
+        if let Some(time_scale) = file.time_scale {
+            self.time_scale = time_scale.clamp(Self::MIN_TIME_SCALE, Self::MAX_TIME_SCALE);
+        }
+        self.view = file.view.map(|SystemView { center, scale }| View {
+            center: center.into(),
+            scale: scale.clamp(View::MIN_SCALE, View::MAX_SCALE),
+        });
+        Ok(())
+    }
+
+    /// Saves the simulation to [`Self::system_path`], with the start date, time scale and view.
+    fn save_system(&self) -> Result<(), SystemFileError> {
+        let mut file = SystemFile::from_simulation(&self.simulation);
+        file.epoch = Some(self.epoch);
+        file.time_scale = Some(self.time_scale);
+        file.view = self.view.as_ref().map(|view| SystemView {
+            center: view.center.into(),
+            scale: view.scale,
+        });
+        std::fs::write(&self.system_path, file.to_ron()?).map_err(SystemFileError::Io)
+    }
+
+    /// Replaces the simulation with the bodies in the Horizons table at
+    /// [`Self::horizons_path`], setting the epoch to the table's date.
+    fn import_horizons(&mut self) -> Result<(), String> {
+        let csv = std::fs::read_to_string(&self.horizons_path)
+            .map_err(|error| format!("Couldn't access the file: {error}"))?;
+        self.load_horizons(&csv)
+    }
+
+    /// Replaces the simulation with the bodies in the Horizons table `csv`, setting the epoch to
+    /// the table's date.
+    fn load_horizons(&mut self, csv: &str) -> Result<(), String> {
+        let bodies = load_horizons(csv).map_err(|error| error.to_string())?;
+        self.load(Simulation::new(bodies));
+        if let Some(epoch) = horizons_epoch(csv) {
+            self.epoch = epoch;
+        }
+        Ok(())
+    }
+
+    /// Adds the bodies in the Horizons table `csv` to the simulation, around the bodies of the
+    /// same name as their tables' centers, taking their states as the current ones. A table
+    /// centered on anything not in the simulation, such as the solar system barycenter, is
+    /// placed around the origin.
+    fn add_horizons(&mut self, csv: &str) -> Result<(), String> {
+        let targets = horizons_targets(csv).map_err(|error| error.to_string())?;
+        self.record_history();
+        for HorizonsTarget { mut body, center } in targets {
+            let center = center.and_then(|center| {
+                self.simulation
+                    .bodies()
+                    .iter()
+                    .find(|body| body.name == center)
+            });
+            if let Some(center) = center {
+                body.position += center.position;
+                body.velocity += center.velocity;
+            }
+            self.simulation.add(body);
+        }
+        Ok(())
+    }
+
+    /// The body called Earth, if the simulation has one.
+    fn earth(&self) -> Option<&Body> {
+        self.simulation
+            .bodies()
+            .iter()
+            .find(|body| body.name == "Earth")
+    }
+
+    /// Adds the satellites in the two-line element sets `text` as test particles around Earth,
+    /// where `propagation` has them on the simulation's current date.
+    fn add_satellites(&mut self, text: &str, propagation: Propagation) -> Result<(), String> {
+        let sets = parse_tles(text).map_err(|error| error.to_string())?;
+        let earth = self
+            .earth()
+            .ok_or("There's no Earth in the system to put the satellites around")?;
+        let date = self.epoch + self.simulation.elapsed_seconds() / SECONDS_PER_DAY;
+        let satellites: Vec<_> = sets
+            .iter()
+            .zip(SATELLITE_COLORS.iter().cycle())
+            .map(|(set, &color)| {
+                Body::from_elements(
+                    earth,
+                    &set.name,
+                    SATELLITE_MASS_KG,
+                    SATELLITE_RADIUS_KM,
+                    color,
+                    &set.elements_on(date, propagation),
+                )
+                // Drawn tilted as Earth is, to stay beside it.
+                .inclined(earth.inclination_deg)
+                .gravitating(false)
+            })
+            .collect();
+        self.record_history();
+        for satellite in satellites {
+            self.simulation.add(satellite);
+        }
+        Ok(())
+    }
+
+    /// Adds a test particle put at a Lagrange point, to watch whether it stays.
+    fn add_lagrange_particle(
+        &mut self,
+        name: &str,
+        position: DVec2,
+        velocity: DVec2,
+        inclination_deg: f32,
+    ) {
+        self.record_history();
+        let particle = Body::new(
+            name,
+            LAGRANGE_PARTICLE_MASS_KG,
+            LAGRANGE_PARTICLE_RADIUS_KM,
+            position,
+            velocity,
+            LAGRANGE_PARTICLE_COLOR,
+        );
+        self.simulation
+            .add(particle.inclined(inclination_deg).gravitating(false));
+    }
+
+    /// Replaces the system with `primary` and `secondary` alone, on a circular orbit, as a
+    /// restricted three-body problem seen turning with them.
+    fn start_cr3bp(&mut self, [primary, secondary]: [BodyId; 2]) {
+        let (Some(primary), Some(secondary)) = (
+            self.simulation.body(primary),
+            self.simulation.body(secondary),
+        ) else {
+            return;
+        };
+        let (problem, bodies) = Cr3bp::set_up(primary, secondary);
+        self.load(Simulation::new(bodies.to_vec()));
+        self.frame = Frame::CoRotating(problem.primaries);
+        // Curves closing at L1, the way out of the secondary's neighborhood.
+        self.jacobi_constant =
+            problem.jacobi_constant(problem.lagrange_points()[0], DVec2::default());
+        self.cr3bp = Some(problem);
+    }
+
+    /// Shows the restricted three-body window, if open: picking the primaries to start with,
+    /// then the problem's units, particles to add at its Lagrange points and the zero-velocity
+    /// curves to draw.
+    fn cr3bp_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_cr3bp;
+        let mut start = None;
+        let mut add = None;
+        let mut leave = false;
+        let bodies: Vec<_> = self
+            .simulation
+            .bodies()
+            .iter()
+            .map(|body| (body.id, body.name.clone()))
+            .collect();
+        let elapsed = self.simulation.elapsed_seconds();
+        Window::new("Restricted three-body problem")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(problem) = self.cr3bp else {
+                    ui.label(
+                        "Two primaries on a circular orbit, and test particles moving under their \
+                         pull, followed in the frame turning with them.",
+                    );
+                    if self.cr3bp_primaries == [None; 2] {
+                        self.cr3bp_primaries = match self.frame {
+                            Frame::CoRotating(pair) => pair.map(Some),
+                            _ => self
+                                .selected()
+                                .and_then(|body| {
+                                    let index = self.simulation.index_of(body.id)?;
+                                    let primary = self.simulation.primary_of(index)?;
+                                    Some([
+                                        Some(self.simulation.bodies()[primary].id),
+                                        Some(body.id),
+                                    ])
+                                })
+                                .unwrap_or_default(),
+                        };
+                    }
+                    Grid::new("cr3bp_primaries").show(ui, |ui| {
+                        for (label, pick) in ["Primary:", "Secondary:"]
+                            .into_iter()
+                            .zip(&mut self.cr3bp_primaries)
+                        {
+                            ui.label(label);
+                            let selected = bodies
+                                .iter()
+                                .find(|&&(id, _)| Some(id) == *pick)
+                                .map_or("—", |(_, name)| name);
+                            egui::ComboBox::from_id_salt(label)
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for (id, name) in &bodies {
+                                        ui.selectable_value(pick, Some(*id), name);
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+                    let pair = match self.cr3bp_primaries {
+                        [Some(a), Some(b)] if a != b => Some([a, b]),
+                        _ => None,
+                    };
+                    if ui
+                        .add_enabled(pair.is_some(), egui::Button::new("Start"))
+                        .on_hover_text(
+                            "Replaces the system with the two primaries alone, as far apart as \
+                             they are now",
+                        )
+                        .clicked()
+                    {
+                        start = pair;
+                    }
+                    return;
+                };
+                let units = self.units;
+                let selected = self
+                    .selected()
+                    .filter(|body| !problem.primaries.contains(&body.id))
+                    .map(|body| {
+                        let (position, velocity) =
+                            problem.to_rotating(elapsed, body.position, body.velocity);
+                        problem.jacobi_constant(position, velocity)
+                    });
+                Grid::new("cr3bp").show(ui, |ui| {
+                    ui.label("Mass ratio μ:");
+                    ui.label(RichText::new(format!("{:.6}", problem.mass_ratio)).monospace());
+                    ui.end_row();
+                    ui.label("Unit of length:");
+                    ui.label(RichText::new(units.distance(problem.distance_m)).monospace());
+                    ui.end_row();
+                    ui.label("Unit of time:");
+                    ui.label(
+                        RichText::new(format_duration(1. / problem.angular_velocity as f32))
+                            .monospace(),
+                    )
+                    .on_hover_text("The primaries go around once every 2π of these");
+                    ui.end_row();
+                    ui.label("Selected particle's C:");
+                    ui.label(
+                        RichText::new(selected.map_or("—".to_string(), |c| format!("{c:.6}")))
+                            .monospace(),
+                    )
+                    .on_hover_text("Its Jacobi constant, which stays the same along its path");
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Particle at:");
+                    for (i, point) in problem.lagrange_points().into_iter().enumerate() {
+                        if ui
+                            .button(format!("L{}", i + 1))
+                            .on_hover_text(
+                                "Adds a test particle there, standing still in the frame",
+                            )
+                            .clicked()
+                        {
+                            add = Some((i + 1, point));
+                        }
+                    }
+                });
+                ui.separator();
+                ui.checkbox(&mut self.show_zero_velocity_curves, "Zero-velocity curves")
+                    .on_hover_text(
+                        "Where particles with the Jacobi constant below would come to a stop. \
+                         They can't cross into where it's higher.",
+                    );
+                ui.horizontal(|ui| {
+                    ui.label("Jacobi constant:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.jacobi_constant)
+                            .range(0.0..=10.)
+                            .speed(0.001)
+                            .max_decimals(6),
+                    );
+                    if ui
+                        .add_enabled(selected.is_some(), egui::Button::new("Selected particle's"))
+                        .clicked()
+                    {
+                        if let Some(c) = selected {
+                            self.jacobi_constant = c;
+                        }
+                    }
+                });
+                ui.separator();
+                leave = ui
+                    .button("Leave")
+                    .on_hover_text("Carries on as an ordinary N-body simulation")
+                    .clicked();
+            });
+        if let Some(pair) = start {
+            self.start_cr3bp(pair);
+        }
+        if let Some((problem, (number, point))) = self.cr3bp.zip(add) {
+            let (position, velocity) = problem.from_rotating(elapsed, point, DVec2::default());
+            self.add_lagrange_particle(&format!("L{number}"), position, velocity, 0.);
+        }
+        if leave {
+            self.cr3bp = None;
+        }
+        self.show_cr3bp = open;
+    }
+
+    /// Looks at the space around Earth from Earth's frame, out to just past geostationary orbit
+    /// on a plot of `size`, and shortens the timestep to suit low orbits.
+    fn earth_orbit_view(&mut self, size: Vec2) {
+        let Some(earth) = self.earth() else {
+            return;
+        };
+        self.frame = Frame::Centered(earth.id);
+        self.follow = false;
+        self.view = Some(View {
+            center: Vec2::ZERO,
+            scale: (2. * EARTH_ORBIT_VIEW_M / size.min_elem())
+                .clamp(View::MIN_SCALE, View::MAX_SCALE),
+        });
+        self.view_velocity = Default::default();
+        let simulation = &mut self.simulation;
+        simulation.timestep_s = simulation.timestep_s.min(LOW_ORBIT_TIMESTEP_S);
+        simulation.max_timestep_s = simulation.max_timestep_s.min(LOW_ORBIT_TIMESTEP_S);
+        simulation.min_timestep_s = simulation.min_timestep_s.min(simulation.max_timestep_s);
+    }
+
+    /// Runs a copy of the simulation for `duration_s`, recording the selected bodies every
+    /// [`Self::trajectory_interval_steps`] steps, and writes what it recorded to
+    /// [`Self::trajectory_path`]. The simulation itself is left where it is.
+    fn export_trajectories(&self, duration_s: f64) -> Result<(), String> {
+        if self.selection.is_empty() {
+            return Err("Select the bodies to record first".to_string());
+        }
+        let mut run = self.simulation.duplicate();
+        run.record_trajectory(&self.selection, self.trajectory_interval_steps);
+        run.run_for(duration_s, self.integrator);
+        run.trajectory
+            .as_ref()
+            .map_or(Ok(()), |trajectory| trajectory.save(&self.trajectory_path))
+            .map_err(|error| format!("Couldn't write the trajectories: {error}"))
+    }
+
+    fn export_trajectory(&mut self) -> std::io::Result<()> {
+        if let Some(trajectory) = &mut self.simulation.trajectory {
+            trajectory.save(&self.trajectory_path)?;
+            if self.clear_trajectory_on_export {
+                trajectory.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the screenshot requested for the [`ImageExport`] in progress, if it has arrived, and
+    /// saves the image once it has them all.
+    fn receive_screenshot(&mut self, ctx: &egui::Context) {
+        self.receive_animation_frame(ctx);
+        let Some(export) = &mut self.image_export else {
+            return;
+        };
+        let screenshot = ctx.input(|i| {
+            i.raw.events.iter().find_map(|event| match event {
+                Event::Screenshot {
+                    user_data, image, ..
+                } => {
+                    let tile = *user_data.data.as_ref()?.downcast_ref::<u32>()?;
+                    Some((tile, image.clone()))
+                }
+                _ => None,
+            })
+        });
+        let Some((tile, image)) = screenshot else {
+            return;
+        };
+        if export.receive(tile, &image, ctx.pixels_per_point()) {
+            if let Err(error) = export.save() {
+                self.error = Some(format!("Couldn't write the image: {error}"));
+            }
+            self.view = Some(export.view.clone());
+            self.image_export = None;
+        }
+    }
+
+    /// Adds the frame requested for the [`AnimationRecording`] in progress, if it has arrived,
+    /// and runs the simulation on to the next one, or finishes the animation if it was the last.
+    fn receive_animation_frame(&mut self, ctx: &egui::Context) {
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+        let screenshot = ctx.input(|i| {
+            i.raw.events.iter().find_map(|event| match event {
+                Event::Screenshot {
+                    user_data, image, ..
+                } => {
+                    let AnimationFrame(frame) = user_data.data.as_ref()?.downcast_ref()?;
+                    Some((*frame, image.clone()))
+                }
+                _ => None,
+            })
+        });
+        let Some((frame, image)) = screenshot else {
+            return;
+        };
+        match recording.receive(frame, &image, ctx.pixels_per_point()) {
+            Ok(false) if recording.captured > frame => {
+                let interval_s = recording.settings.interval_s * self.time_direction();
+                let mergers = self.simulation.run_for(interval_s, self.integrator);
+                self.follow_mergers(mergers);
+            }
+            Ok(false) => {}
+            Ok(true) => self.finish_recording(),
+            Err(error) => {
+                self.error = Some(format!("Couldn't write the animation: {error}"));
+                self.recording = None;
+            }
+        }
+    }
+
+    /// Stops the [`AnimationRecording`] in progress, writing what it has captured.
+    fn finish_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            if let Err(error) = recording.finish() {
+                self.error = Some(format!("Couldn't write the animation: {error}"));
+            }
+        }
+    }
+
+    /// Where the file prompt for `action` reads and writes.
+    fn path_mut(&mut self, action: FileAction) -> &mut String {
+        match action {
+            FileAction::OpenSystem | FileAction::SaveSystem => &mut self.system_path,
+            FileAction::ImportHorizons => &mut self.horizons_path,
+            FileAction::ExportTrajectory => &mut self.trajectory_path,
+            FileAction::ExportChart => &mut self.chart_path,
+            FileAction::ExportImage => &mut self.image_path,
+        }
+    }
+
+    /// The propellant and engine of the body with `id`, if it is a spacecraft, its planned burns,
+    /// and the one being planned along its orbit of `elements`. Otherwise, a button to make it one.
+    fn spacecraft_controls(&mut self, ui: &mut Ui, id: BodyId, elements: Option<OrbitalElements>) {
+        let Some(body) = self.simulation.body(id) else {
+            return;
+        };
+        let Some(mut spacecraft) = body.spacecraft.clone() else {
+            if ui
+                .button("Make spacecraft")
+                .on_hover_text(
+                    "Gives it propellant and an engine, within its mass, to plan burns with",
+                )
+                .clicked()
+            {
+                self.record_history();
+                if let Some(body) = self.simulation.body_mut(id) {
+                    body.spacecraft = Some(Spacecraft::with_mass(body.mass_kg));
+                }
+            }
+            return;
+        };
+        let before = spacecraft.clone();
+        let now = self.simulation.elapsed_seconds();
+        let mut draft = self
+            .maneuver_draft
+            .filter(|&(drafted, _)| drafted == id)
+            .map(|(_, draft)| draft);
+        let had_draft = draft.is_some();
+        let (mut commit, mut discard) = (false, false);
+        ui.label("Spacecraft:");
+        Grid::new("spacecraft").show(ui, |ui| {
+            let kilograms = |ui: &mut Ui, label: &str, kg: &mut f64, min: f64| {
+                ui.label(label);
+                let speed = (*kg * 0.01).max(1.);
+                ui.add(
+                    egui::DragValue::new(kg)
+                        .range(min..=f64::MAX)
+                        .speed(speed)
+                        .custom_formatter(|value, _| format_number(value))
+                        .suffix(" kg"),
+                );
+                ui.end_row();
+            };
+            kilograms(ui, "Dry mass:", &mut spacecraft.dry_mass_kg, 1.);
+            kilograms(ui, "Propellant:", &mut spacecraft.propellant_kg, 0.);
+            ui.label("Specific impulse:");
+            ui.add(
+                egui::DragValue::new(&mut spacecraft.specific_impulse_s)
+                    .range(1.0..=1e5)
+                    .suffix(" s"),
+            );
+            ui.end_row();
+            ui.label("Thrust:");
+            let mut kilonewtons = spacecraft.thrust_n / 1e3;
+            let speed = (kilonewtons * 0.01).max(0.01);
+            if ui
+                .add(
+                    egui::DragValue::new(&mut kilonewtons)
+                        .range(1e-6..=f64::MAX)
+                        .speed(speed)
+                        .custom_formatter(|value, _| format_number(value))
+                        .suffix(" kN"),
+                )
+                .changed()
+            {
+                spacecraft.thrust_n = kilonewtons * 1e3;
+            }
+            ui.end_row();
+            ui.label("Δv left:");
+            ui.label(RichText::new(format!("{:.1} m/s", spacecraft.delta_v_budget())).monospace());
+            ui.end_row();
+        });
+        if !spacecraft.maneuvers.is_empty() {
+            ui.label("Planned burns:");
+            let mut removed = None;
+            for (index, maneuver) in spacecraft.maneuvers.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text("Cancel the burn")
+                        .clicked()
+                    {
+                        removed = Some(index);
+                    }
+                    ui.label(format!(
+                        "{:.1} m/s in {}",
+                        maneuver.delta_v.length(),
+                        format_duration((maneuver.elapsed_seconds - now).max(0.) as f32)
+                    ));
+                });
+            }
+            if let Some(index) = removed {
+                spacecraft.maneuvers.remove(index);
+            }
+        }
+        // The next periapsis and apoapsis, to put the node at.
+        let periapsis_s = elements.and_then(|elements| elements.time_to_periapsis_s());
+        let apoapsis_s = periapsis_s
+            .zip(elements.and_then(|elements| elements.period_s))
+            .map(|(periapsis_s, period_s)| (periapsis_s + period_s / 2.) % period_s);
+        match &mut draft {
+            None => {
+                if ui
+                    .button("New maneuver node")
+                    .on_hover_text("Plans a burn, previewing the path it leads to")
+                    .clicked()
+                {
+                    draft = Some(Maneuver {
+                        elapsed_seconds: now + periapsis_s.unwrap_or(HOUR_S as f64),
+                        delta_v: DVec2::ZERO,
+                    });
+                }
+            }
+            Some(maneuver) => {
+                // Time runs on while the burn is planned.
+                maneuver.elapsed_seconds = maneuver.elapsed_seconds.max(now);
+                Grid::new("maneuver").show(ui, |ui| {
+                    ui.label("Burn in:");
+                    ui.horizontal(|ui| {
+                        let mut ahead_s = maneuver.elapsed_seconds - now;
+                        let speed = ahead_s.max(MINUTE_S as f64) * 0.01;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut ahead_s)
+                                    .range(0.0..=f64::MAX)
+                                    .speed(speed)
+                                    .custom_formatter(|seconds, _| format_duration(seconds as f32)),
+                            )
+                            .changed()
+                        {
+                            maneuver.elapsed_seconds = now + ahead_s;
+                        }
+                        for (label, ahead_s) in
+                            [("Periapsis", periapsis_s), ("Apoapsis", apoapsis_s)]
+                        {
+                            if let Some(ahead_s) = ahead_s {
+                                if ui.small_button(label).clicked() {
+                                    maneuver.elapsed_seconds = now + ahead_s;
+                                }
+                            }
+                        }
+                    });
+                    ui.end_row();
+                    for (label, component) in [
+                        ("Prograde:", &mut maneuver.delta_v.x),
+                        ("Radial out:", &mut maneuver.delta_v.y),
+                    ] {
+                        ui.label(label);
+                        ui.add(egui::DragValue::new(component).speed(1.).suffix(" m/s"));
+                        ui.end_row();
+                    }
+                });
+                let delta_v = maneuver.delta_v.length();
+                let text = if delta_v > spacecraft.delta_v_budget() {
+                    format!("{delta_v:.1} m/s: more than the propellant left is good for")
+                } else {
+                    format!(
+                        "{delta_v:.1} m/s: {} kg of propellant, over {}",
+                        format_number(spacecraft.propellant_for(delta_v)),
+                        format_duration(spacecraft.burn_duration_s(delta_v) as f32)
+                    )
+                };
+                ui.label(text);
+                ui.horizontal(|ui| {
+                    commit = ui
+                        .button("Commit")
+                        .on_hover_text("Plans the burn, to be made when its time comes")
+                        .clicked();
+                    discard = ui.button("Discard").clicked();
+                });
+            }
+        }
+        if spacecraft != before {
+            let time = ui.input(|i| i.time);
+            self.history
+                .record_edit(&self.simulation, &self.selection, time);
+        }
+        if let Some(maneuver) = draft.filter(|_| commit) {
+            if spacecraft == before {
+                self.record_history();
+            }
+            spacecraft.plan(maneuver);
+        }
+        if let Some(body) = self.simulation.body_mut(id) {
+            body.mass_kg = spacecraft.mass_kg();
+            body.spacecraft = Some(spacecraft);
+        }
+        if commit || discard {
+            draft = None;
+        }
+        if had_draft || draft.is_some() {
+            self.maneuver_draft = draft.map(|draft| (id, draft));
+        }
+    }
+
+    /// Starts, pauses and exports the recording of `body`'s trajectory.
+    fn trajectory_controls(&mut self, ui: &mut Ui, body: BodyId) {
+        let trajectory = self
+            .simulation
+            .trajectory
+            .as_mut()
+            .filter(|trajectory| trajectory.is_of(body));
+        let mut recording = trajectory
+            .as_ref()
+            .is_some_and(|trajectory| trajectory.recording);
+        if ui.checkbox(&mut recording, "Record trajectory").changed() {
+            match trajectory {
+                Some(trajectory) => trajectory.recording = recording,
+                None => self
+                    .simulation
+                    .record_trajectory(&[body], self.trajectory_interval_steps),
+            }
+        }
+        let interval = ui
+            .add(
+                egui::Slider::new(&mut self.trajectory_interval_steps, 1..=1000)
+                    .logarithmic(true)
+                    .custom_formatter(|steps, _| {
+                        format_duration((steps * self.simulation.timestep_s) as f32)
+                    })
+                    .text("Sample every"),
+            )
+            .changed();
+        let Some(trajectory) = self
+            .simulation
+            .trajectory
+            .as_mut()
+            .filter(|trajectory| trajectory.is_of(body))
+        else {
+            return;
+        };
+        if interval {
+            trajectory.interval_steps = self.trajectory_interval_steps;
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!("{} samples", trajectory.len()));
+            if ui
+                .add_enabled(!trajectory.is_empty(), egui::Button::new("Export CSV…"))
+                .clicked()
+            {
+                self.file_prompt = Some(FileAction::ExportTrajectory);
+            }
+        });
+        ui.checkbox(&mut self.clear_trajectory_on_export, "Clear after export");
+    }
+
+    /// Where `body` will be after each of the next `steps` steps of `dt` seconds, in the reference
+    /// frame, found by running a copy of the simulation so the live one is untouched, with
+    /// `draft` added to the burns planned if it is a spacecraft. Stops early if a body the frame
+    /// follows is lost.
+    fn predict_path(
+        &self,
+        body: &Body,
+        steps: usize,
+        dt: f64,
+        draft: Option<Maneuver>,
+    ) -> Vec<DVec2> {
+        let mut forecast = self.simulation.duplicate();
+        forecast.trail_duration_s = 0.;
+        if let Some((spacecraft, draft)) = forecast
+            .body_mut(body.id)
+            .and_then(|body| body.spacecraft.as_mut())
+            .zip(draft)
+        {
+            spacecraft.plan(draft);
+        }
+        let mut tracked = body.id;
+        let mut frame = self.frame;
+        (0..steps)
+            .map_while(|_| {
+                for merger in forecast.step(dt, self.integrator) {
+                    frame.follow_merger(&merger);
+                    if merger
+                        .originals
+                        .iter()
+                        .any(|original| original.id == tracked)
+                    {
+                        tracked = merger.merged;
+                    }
+                }
+                let transform = frame.transform(&forecast, 0.)?;
+                Some(transform.apply(forecast.body(tracked)?.position))
+            })
+            .collect()
+    }
+
+    /// Recomputes the selected body's predicted path if anything it depends on has changed. While
+    /// a burn is being planned for it, the path runs far enough to show where it leads.
+    fn update_prediction(&mut self) {
+        let draft = self
+            .maneuver_draft
+            .filter(|(id, _)| self.selection == [*id])
+            .map(|(_, draft)| draft);
+        let Some(body) = self
+            .selected()
+            .filter(|_| self.prediction_steps > 0 || draft.is_some())
+        else {
+            self.prediction = None;
+            return;
+        };
+        let now = self.simulation.elapsed_seconds();
+        let dt = self.simulation.timestep_s;
+        // The index of the point the step reaching `elapsed_seconds` ends on.
+        let index =
+            |elapsed_seconds: f64| ((elapsed_seconds - now) / dt).ceil().max(1.) as usize - 1;
+        let mut steps = self.prediction_steps;
+        if let Some(draft) = draft {
+            let period_s = self
+                .simulation
+                .index_of(body.id)
+                .and_then(|index| self.simulation.primary_of(index))
+                .and_then(|primary| {
+                    body.orbital_elements(&self.simulation.bodies()[primary])
+                        .period_s
+                });
+            let after = period_s.map_or(Self::MANEUVER_PREVIEW_STEPS, |period_s| {
+                (period_s / dt).ceil() as usize
+            });
+            steps = steps
+                .max(index(draft.elapsed_seconds) + after)
+                .min(Self::MAX_MANEUVER_PREVIEW_STEPS);
+        }
+        let maneuvers = body
+            .spacecraft
+            .as_ref()
+            .map(|spacecraft| spacecraft.maneuvers.clone())
+            .unwrap_or_default();
+        let key = PredictionKey {
+            body: body.id,
+            mass_kg: body.mass_kg,
+            position: body.position,
+            velocity: body.velocity,
+            elapsed_seconds: self.simulation.elapsed_seconds(),
+            body_count: self.simulation.bodies().len(),
+            steps,
+            timestep_s: dt,
+            integrator: self.integrator,
+            frame: self.frame,
+            maneuvers: maneuvers.clone(),
+            draft,
+        };
+        if self
+            .prediction
+            .as_ref()
+            .map_or(true, |prediction| prediction.key != key)
+        {
+            let path = self.predict_path(body, steps, dt, draft);
+            let on_path = |index: usize| Some(index).filter(|&index| index < path.len());
+            let nodes = maneuvers
+                .iter()
+                .chain(&draft)
+                .filter_map(|maneuver| on_path(index(maneuver.elapsed_seconds)))
+                .collect();
+            let draft = draft.and_then(|draft| on_path(index(draft.elapsed_seconds)));
+            self.prediction = Some(Prediction {
+                key,
+                path,
+                nodes,
+                draft,
+            });
+        }
+    }
+
+    /// Selects the body `offset` places after the selected one, wrapping around, or the first
+    /// body if none is selected.
+    fn cycle_selection(&mut self, offset: isize) {
+        let bodies = self.simulation.bodies();
+        if bodies.is_empty() {
+            return;
+        }
+        let index = match self
+            .selected()
+            .and_then(|selected| self.simulation.index_of(selected.id))
+        {
+            Some(index) => (index as isize + offset).rem_euclid(bodies.len() as isize) as usize,
+            None => 0,
+        };
+        self.selection = vec![bodies[index].id];
+    }
+
+    /// The selected bodies that still exist.
+    fn selected_bodies(&self) -> Vec<&Body> {
+        self.selection
+            .iter()
+            .filter_map(|&id| self.simulation.body(id))
+            .collect()
+    }
+
+    /// Drags out a box with the secondary button, and on release selects the bodies inside it,
+    /// adding them to the selection if `add` is held.
+    fn box_select(
+        &mut self,
+        ui: &Ui,
+        response: &Response,
+        transform: &PlotTransform,
+        add: bool,
+        palette: &Palette,
+    ) {
+        if response.drag_started_by(PointerButton::Secondary) {
+            self.selection_box = ui
+                .input(|i| i.pointer.press_origin())
+                .map(|origin| [origin; 2]);
+        }
+        let Some([origin, corner]) = &mut self.selection_box else {
+            return;
+        };
+        if let Some(pointer) = ui.input(|i| i.pointer.interact_pos()) {
+            *corner = pointer;
+        }
+        let rect = Rect::from_two_pos(*origin, *corner);
+        if response.dragged_by(PointerButton::Secondary) {
+            ui.painter().rect(
+                rect,
+                0.,
+                palette.highlight.gamma_multiply(0.1),
+                Stroke::new(1., palette.highlight),
+            );
+            return;
+        }
+        self.selection_box = None;
+        if !add {
+            self.selection.clear();
+        }
+        let inside: Vec<_> = self
+            .simulation
+            .bodies()
+            .iter()
+            .filter(|body| rect.contains(self.projection.body_screen_position(transform, body)))
+            .filter(|body| !self.is_selected(body))
+            .map(|body| body.id)
+            .collect();
+        self.selection.extend(inside);
+    }
+
+    /// Totals for a selection of several bodies.
+    fn selection_window(&mut self, ctx: &egui::Context) {
+        let bodies = self.selected_bodies();
+        let mass_kg: f64 = bodies.iter().map(|body| body.mass_kg).sum();
+        let [moment, momentum] =
+            bodies
+                .iter()
+                .fold([DVec2::ZERO; 2], |[moment, momentum], body| {
+                    let mass_kg = body.mass_kg;
+                    [
+                        moment + body.position * mass_kg,
+                        momentum + body.velocity * mass_kg,
+                    ]
+                });
+        let names: Vec<_> = bodies
+            .iter()
+            .map(|body| (body.id, RichText::new(body.name.as_str()).color(body.color)))
+            .collect();
+        let mut delete = false;
+        Window::new(format!("{} bodies", names.len()))
+            .id(Id::new("selection"))
+            .anchor(Align2::CENTER_TOP, [0., 10.])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("selection_stats").show(ui, |ui| {
+                    let mut row = |label: &str, value: String| {
+                        ui.label(label);
+                        ui.label(RichText::new(value).monospace());
+                        ui.end_row();
+                    };
+                    let units = self.units;
+                    row("Total mass:", units.mass(mass_kg));
+                    if mass_kg > 0. {
+                        let center = moment / mass_kg;
+                        row(
+                            "Center of mass:",
+                            format!(
+                                "({}, {})",
+                                units.distance(center.x),
+                                units.distance(center.y)
+                            ),
+                        );
+                        row("Velocity:", units.speed((momentum / mass_kg).length()));
+                    }
+                    row(
+                        "Momentum:",
+                        format!("({:.3e}, {:.3e}) kg m/s", momentum.x, momentum.y),
+                    );
+                });
+                ui.separator();
+                ui.horizontal_wrapped(|ui| {
+                    for (id, name) in &names {
+                        if ui.selectable_label(false, name.clone()).clicked() {
+                            self.selection = vec![*id];
+                        }
+                    }
+                });
+                ui.separator();
+                if ui.button("Delete all").clicked() {
+                    delete = true;
+                }
+            });
+        if delete {
+            self.delete(&self.selection.clone());
+        }
+    }
+
+    /// The selected body, if exactly one is selected.
+    fn selected(&self) -> Option<&Body> {
+        match self.selection.as_slice() {
+            [selected] => self.simulation.body(*selected),
+            _ => None,
+        }
+    }
+
+    fn is_selected(&self, body: &Body) -> bool {
+        self.selection.contains(&body.id)
+    }
+
+    /// Adds `body` to the selection, or takes it out if it's already in.
+    fn toggle_selected(&mut self, body: BodyId) {
+        if self.selection.contains(&body) {
+            self.selection.retain(|&selected| selected != body);
+        } else {
+            self.selection.push(body);
+        }
+    }
+
+    /// Selects and follows `body`, zooming to take in its satellites, or a tenth of the way to its
+    /// primary if it has none.
+    fn frame(&mut self, id: BodyId, size: Vec2) {
+        let (Some(index), Some(body)) = (self.simulation.index_of(id), self.simulation.body(id))
+        else {
+            return;
+        };
+        let bodies = self.simulation.bodies();
+        let center = self.projection.body(body);
+        let distance_to = |other: &Body| (self.projection.body(other) - center).length();
+        let satellites = self.simulation.satellites_of(index);
+        let extent = if satellites.is_empty() {
+            self.simulation
+                .primary_of(index)
+                .map(|primary| 0.1 * distance_to(&bodies[primary]))
+        } else {
+            satellites
+                .into_iter()
+                .map(|satellite| distance_to(&bodies[satellite]))
+                .reduce(f32::max)
+        };
+        let view = self
+            .view
+            .get_or_insert_with(|| View::fit(bodies, self.projection, size));
+        view.center = center;
+        if let Some(extent) = extent.filter(|&extent| extent > 0.) {
+            view.scale = (2.2 * extent / size.min_elem()).clamp(View::MIN_SCALE, View::MAX_SCALE);
+        }
+        self.view_velocity = Default::default();
+        self.selection = vec![id];
+        self.follow = true;
+    }
+
+    /// Shows the "Paste Horizons vectors" dialog, if open, loading or adding the table pasted
+    /// into it when asked.
+    fn horizons_paste_dialog(&mut self, ctx: &egui::Context) {
+        let Some(text) = &mut self.horizons_paste else {
+            return;
+        };
+        let mut open = true;
+        let (mut replace, mut add) = (false, false);
+        Window::new("Paste Horizons vectors")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                ui.label("A vector table in CSV format, from $$SOE to $$EOE with its header:");
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(text)
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.horizontal(|ui| {
+                    replace = ui
+                        .button("Replace system")
+                        .on_hover_text("Loads just the table's bodies, at the table's date")
+                        .clicked();
+                    add = ui
+                        .button("Add to system")
+                        .on_hover_text(
+                            "Adds the table's bodies around the bodies they are centered on, as \
+                             they are now",
+                        )
+                        .clicked();
+                });
+            });
+        if replace || add {
+            let text = std::mem::take(text);
+            let result = if replace {
+                self.load_horizons(&text)
+            } else {
+                self.add_horizons(&text)
+            };
+            match result {
+                Ok(()) => open = false,
+                Err(error) => self.error = Some(error),
+            }
+            if let Some(paste) = &mut self.horizons_paste {
+                *paste = text;
+            }
+        }
+        if !open {
+            self.horizons_paste = None;
+        }
+    }
+
+    /// Shows the "Add satellites from TLEs" dialog, if open, adding the satellites pasted into it
+    /// when asked.
+    fn tle_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.tle_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut add = false;
+        Window::new("Add satellites from TLEs")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                ui.label("Two-line element sets, each optionally after a line with its name:");
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut dialog.text)
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                egui::ComboBox::from_label("Propagation to the current date")
+                    .selected_text(dialog.propagation.name())
+                    .show_ui(ui, |ui| {
+                        for propagation in Propagation::ALL {
+                            ui.selectable_value(
+                                &mut dialog.propagation,
+                                propagation,
+                                propagation.name(),
+                            );
+                        }
+                    });
+                ui.checkbox(&mut dialog.earth_view, "Switch to the Earth orbit view");
+                add = ui.button("Add").clicked();
+            });
+        if add {
+            let text = std::mem::take(&mut dialog.text);
+            let (propagation, earth_view) = (dialog.propagation, dialog.earth_view);
+            match self.add_satellites(&text, propagation) {
+                Ok(()) => {
+                    open = false;
+                    if earth_view {
+                        self.earth_orbit_view(ctx.screen_rect().size());
+                    }
+                }
+                Err(error) => self.error = Some(error),
+            }
+            if let Some(dialog) = &mut self.tle_dialog {
+                dialog.text = text;
+            }
+        }
+        if !open {
+            self.tle_dialog = None;
+        }
+    }
+
+    /// The name of the body `id`, or a dash if it is gone.
+    fn body_name(&self, id: BodyId) -> String {
+        self.simulation
+            .body(id)
+            .map_or("—".to_string(), |body| body.name.clone())
+    }
+
+    /// Adds `events` to the log, pausing if asked to.
+    fn log_events(&mut self, events: Vec<AlignmentEvent>) {
+        for AlignmentEvent {
+            alignment,
+            kind,
+            elapsed_seconds,
+        } in events
+        {
+            let mut description = format!(
+                "{} of {}, from {}",
+                kind.name(),
+                self.body_name(alignment.target),
+                self.body_name(alignment.observer),
+            );
+            let reference = self.body_name(alignment.reference);
+            if reference != "Sun" {
+                description += &format!(", with {reference}");
+            }
+            let date = self.epoch + elapsed_seconds / SECONDS_PER_DAY;
+            self.event_log.push((date, description));
+            self.paused |= self.pause_on_events;
+        }
+    }
+
+    /// Shows the events window, if open: the alignments watched for, picking more, and the log
+    /// of those that have come about.
+    fn events_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_events;
+        let mut unwatch = None;
+        let mut watch = None;
+        let bodies: Vec<_> = self
+            .simulation
+            .bodies()
+            .iter()
+            .map(|body| (body.id, body.name.clone()))
+            .collect();
+        let find = |name: &str| {
+            bodies
+                .iter()
+                .find(|(_, body)| body == name)
+                .map(|&(id, _)| id)
+        };
+        let [observer, _, reference] = &mut self.new_alignment;
+        // Earth and the Sun to begin with, for the planets' oppositions and conjunctions.
+        *observer = observer.or_else(|| find("Earth"));
+        *reference = reference.or_else(|| find("Sun"));
+        Window::new("Events")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(320.)
+            .show(ctx, |ui| {
+                ui.label("Watching for conjunctions and oppositions of:");
+                for (index, alignment) in self.alignments.alignments().iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .small_button("✖")
+                            .on_hover_text("Stop watching")
+                            .clicked()
+                        {
+                            unwatch = Some(index);
+                        }
+                        ui.label(format!(
+                            "{} from {}, against {}",
+                            self.body_name(alignment.target),
+                            self.body_name(alignment.observer),
+                            self.body_name(alignment.reference),
+                        ));
+                    });
+                }
+                Grid::new("new_alignment").show(ui, |ui| {
+                    for (label, pick) in ["From:", "Watch:", "Against:"]
+                        .into_iter()
+                        .zip(&mut self.new_alignment)
+                    {
+                        ui.label(label);
+                        let selected = bodies
+                            .iter()
+                            .find(|&&(id, _)| Some(id) == *pick)
+                            .map_or("—", |(_, name)| name);
+                        egui::ComboBox::from_id_salt(label)
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for (id, name) in &bodies {
+                                    ui.selectable_value(pick, Some(*id), name);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+                if let [Some(observer), Some(target), Some(reference)] = self.new_alignment {
+                    let distinct =
+                        observer != target && target != reference && reference != observer;
+                    if ui
+                        .add_enabled(distinct, egui::Button::new("Watch"))
+                        .clicked()
+                    {
+                        watch = Some(Alignment {
+                            observer,
+                            target,
+                            reference,
+                        });
+                    }
+                }
+                ui.checkbox(&mut self.pause_on_events, "Pause when one comes about");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} events", self.event_log.len()));
+                    if ui.button("Clear").clicked() {
+                        self.event_log.clear();
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(200.)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for (date, description) in &self.event_log {
+                            ui.label(format!(
+                                "{}  {description}",
+                                DateTime::from_julian_date(*date)
+                            ));
+                        }
+                    });
+            });
+        if let Some(index) = unwatch {
+            self.alignments.unwatch(index);
+        }
+        if let Some(alignment) = watch {
+            self.alignments.watch(alignment);
+        }
+        self.show_events = open;
+    }
+
+    /// Adds the current values of the chart's quantity to its series, starting them afresh if
+    /// the quantity or selection has changed.
+    fn update_chart(&mut self) {
+        let bodies: Vec<_> = self
+            .selection
+            .iter()
+            .filter_map(|&id| self.simulation.body(id))
+            .collect();
+        let chart = &mut self.chart;
+        if chart.quantity != self.chart_quantity || chart.selection != self.selection {
+            chart.quantity = self.chart_quantity;
+            chart.selection.clone_from(&self.selection);
+            let name = |body: &Body| body.name.clone();
+            chart.series = match chart.quantity {
+                ChartQuantity::Separation => bodies
+                    .get(0..2)
+                    .map(|pair| vec![format!("{} to {}", name(pair[0]), name(pair[1]))])
+                    .unwrap_or_default(),
+                ChartQuantity::DistanceFromSun | ChartQuantity::Speed => {
+                    bodies.iter().map(|body| name(body)).collect()
+                }
+            }
+            .into_iter()
+            .map(|name| ChartSeries {
+                name,
+                points: VecDeque::new(),
+            })
+            .collect();
+        }
+        let sun = self
+            .simulation
+            .bodies()
+            .iter()
+            .find(|body| body.name == "Sun");
+        let values: Vec<f64> = match chart.quantity {
+            ChartQuantity::DistanceFromSun => match sun {
+                Some(sun) => bodies
+                    .iter()
+                    .map(|body| (body.position - sun.position).length())
+                    .collect(),
+                None => Vec::new(),
+            },
+            ChartQuantity::Separation => bodies
+                .get(0..2)
+                .map(|pair| vec![(pair[0].position - pair[1].position).length()])
+                .unwrap_or_default(),
+            ChartQuantity::Speed => bodies
+                .iter()
+                .map(|body| {
+                    let frame = self.projection.frame;
+                    frame.apply_velocity(body.velocity).length()
+                })
+                .collect(),
+        };
+        let elapsed = self.simulation.elapsed_seconds();
+        for (series, value) in chart.series.iter_mut().zip(values) {
+            if series
+                .points
+                .back()
+                .map_or(true, |&[last, _]| last != elapsed)
+            {
+                if series.points.len() == Chart::MAX_POINTS {
+                    series.points.pop_front();
+                }
+                series.points.push_back([elapsed, value]);
+            }
+        }
+    }
+
+    /// The chart of [`Self::chart_quantity`] over time, with its controls.
+    fn chart_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("chart_quantity")
+                .selected_text(self.chart_quantity.name())
+                .show_ui(ui, |ui| {
+                    for quantity in ChartQuantity::ALL {
+                        ui.selectable_value(&mut self.chart_quantity, quantity, quantity.name());
+                    }
+                });
+            if ui.button("Clear").clicked() {
+                for series in &mut self.chart.series {
+                    series.points.clear();
+                }
+            }
+            let has_points = self
+                .chart
+                .series
+                .iter()
+                .any(|series| !series.points.is_empty());
+            if ui
+                .add_enabled(has_points, egui::Button::new("Export CSV…"))
+                .clicked()
+            {
+                self.file_prompt = Some(FileAction::ExportChart);
+            }
+            let hint = match self.chart_quantity {
+                _ if self.selection.is_empty() => "Select bodies to chart them.",
+                ChartQuantity::Separation if self.selection.len() < 2 => {
+                    "Select two bodies to chart the distance between them."
+                }
+                ChartQuantity::DistanceFromSun if !self.chart_has_sun() => {
+                    "There is no Sun to measure from."
+                }
+                _ => "Scroll to zoom, drag to pan, double-click to fit.",
+            };
+            ui.weak(hint);
+        });
+        let units = self.units;
+        let quantity = self.chart_quantity;
+        Plot::new("chart")
+            .legend(Legend::default())
+            .x_axis_formatter(|mark, _| {
+                let sign = if mark.value < 0. { "-" } else { "" };
+                format!("{sign}{}", format_duration(mark.value.abs() as f32))
+            })
+            .y_axis_formatter(move |mark, _| match quantity {
+                ChartQuantity::Speed => units.speed(mark.value),
+                _ => units.distance(mark.value),
+            })
+            .show(ui, |ui| {
+                for series in &self.chart.series {
+                    let points: Vec<_> = series.points.iter().copied().collect();
+                    ui.line(Line::new(PlotPoints::new(points)).name(&series.name));
+                }
+            });
+    }
+
+    fn chart_has_sun(&self) -> bool {
+        self.simulation
+            .bodies()
+            .iter()
+            .any(|body| body.name == "Sun")
+    }
+
+    /// Shows the "Record animation" dialog, if open, starting the recording when asked.
+    fn animation_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.animation_dialog;
+        let mut record = false;
+        let settings = &mut self.animation;
+        Window::new("Record animation")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("animation").show(ui, |ui| {
+                    ui.label("Format:");
+                    egui::ComboBox::from_id_salt("animation_format")
+                        .selected_text(settings.format.name())
+                        .show_ui(ui, |ui| {
+                            for format in AnimationFormat::ALL {
+                                ui.selectable_value(&mut settings.format, format, format.name());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("File path:");
+                    ui.text_edit_singleline(&mut settings.path).on_hover_text(
+                        match settings.format {
+                            AnimationFormat::PngSequence => {
+                                "Numbered for each frame, as orbit_0001.png for orbit.png"
+                            }
+                            AnimationFormat::Apng => "A .png file",
+                        },
+                    );
+                    ui.end_row();
+                    ui.label("Frames:");
+                    ui.add(egui::DragValue::new(&mut settings.frames).range(1..=10_000));
+                    ui.end_row();
+                    ui.label("Every:");
+                    ui.add(
+                        egui::Slider::new(&mut settings.interval_s, 1.0..=YEAR_S as f64)
+                            .logarithmic(true)
+                            .custom_formatter(|seconds, _| format_duration(seconds as f32)),
+                    )
+                    .on_hover_text("Simulated time between frames");
+                    ui.end_row();
+                    if settings.format == AnimationFormat::Apng {
+                        ui.label("Playback:");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.frames_per_second)
+                                .range(1..=60)
+                                .suffix(" frames/s"),
+                        );
+                        ui.end_row();
+                    }
+                });
+                record = ui
+                    .add_enabled(!settings.path.is_empty(), egui::Button::new("Record"))
+                    .on_hover_text("Press Escape to stop early")
+                    .clicked();
+            });
+        if record && self.image_export.is_none() {
+            self.recording = Some(AnimationRecording::new(self.animation.clone()));
+            open = false;
+        }
+        self.animation_dialog = open;
+    }
+
+    /// Shows the "Export trajectories" dialog, if open, exporting when asked.
+    fn trajectory_export_dialog(&mut self, ctx: &egui::Context) {
+        let Some(duration_s) = &mut self.trajectory_export else {
+            return;
+        };
+        let mut open = true;
+        let mut export = false;
+        Window::new("Export trajectories")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(match self.selection.len() {
+                    0 => "Select the bodies to record.".to_string(),
+                    1 => "Records the selected body.".to_string(),
+                    count => format!("Records the {count} selected bodies."),
+                });
+                Grid::new("trajectory_export").show(ui, |ui| {
+                    ui.label("Run for:");
+                    let mut days = *duration_s / SECONDS_PER_DAY;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut days)
+                                .range(0.0..=1e6)
+                                .speed(1.)
+                                .suffix(" days"),
+                        )
+                        .changed()
+                    {
+                        *duration_s = days * SECONDS_PER_DAY;
+                    }
+                    ui.end_row();
+                    ui.label("Sample every:");
+                    ui.add(
+                        egui::Slider::new(&mut self.trajectory_interval_steps, 1..=1000)
+                            .logarithmic(true)
+                            .suffix(" steps"),
+                    );
+                    ui.end_row();
+                    ui.label("CSV file path:");
+                    ui.text_edit_singleline(&mut self.trajectory_path);
+                    ui.end_row();
+                });
+                export = ui
+                    .add_enabled(!self.selection.is_empty(), egui::Button::new("Export"))
+                    .on_hover_text(
+                        "Each row has the time, body, position, velocity and acceleration, in \
+                         seconds, meters, m/s and m/s²",
+                    )
+                    .clicked();
+            });
+        if export {
+            let duration_s = *duration_s;
+            match self.export_trajectories(duration_s) {
+                Ok(()) => open = false,
+                Err(error) => self.error = Some(error),
+            }
+        }
+        if !open {
+            self.trajectory_export = None;
+        }
+    }
+
+    /// Shows the "Go to date" dialog, if open, running the simulation forwards or backwards to
+    /// the date picked when asked, and how far it has got while it does.
+    fn date_dialog(&mut self, ctx: &egui::Context) {
+        let Some(date) = &mut self.date_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut go = false;
+        let mut stop = false;
+        Window::new("Go to date")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("date").show(ui, |ui| {
+                    ui.label("Date:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut date.year));
+                        ui.add(egui::DragValue::new(&mut date.month).range(1..=12));
+                        ui.add(egui::DragValue::new(&mut date.day).range(1..=31));
+                    });
+                    ui.end_row();
+                    ui.label("Time:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut date.hour).range(0..=23));
+                        ui.label(":");
+                        ui.add(egui::DragValue::new(&mut date.minute).range(0..=59));
+                    });
+                    ui.end_row();
+                });
+                match self.seek_to {
+                    Some(seek_to) => {
+                        let left = seek_to - self.simulation.elapsed_seconds();
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!("{} to go", format_duration(left.abs() as f32)));
+                            stop = ui.button("Stop").clicked();
+                        });
+                    }
+                    None => {
+                        go = ui
+                            .button("Go")
+                            .on_hover_text(
+                                "Runs the simulation there, backwards if the date has passed. \
+                                 Collisions along the way can't be run back.",
+                            )
+                            .clicked();
+                    }
+                }
+            });
+        if go {
+            let seek_to = (date.julian_date() - self.epoch) * SECONDS_PER_DAY;
+            self.record_history();
+            self.seek_to = Some(seek_to);
+        }
+        if stop || !open {
+            self.seek_to = None;
+        }
+        if !open {
+            self.date_dialog = None;
+        }
+    }
+
+    /// Carries on with the closest-approach search, if one is running, for a frame's worth of
+    /// physics at most.
+    fn search_approach(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.approach_dialog else {
+            return;
+        };
+        let Some(search) = &mut dialog.search else {
+            return;
+        };
+        let deadline = Instant::now() + Self::PHYSICS_BUDGET;
+        match search.run_while(self.integrator, || Instant::now() < deadline) {
+            Some(outcome) => {
+                dialog.outcome = Some((search.bodies(), outcome));
+                dialog.search = None;
+            }
+            None => ctx.request_repaint(),
+        }
+    }
+
+    /// Shows the closest-approach dialog, if open: picking two bodies, searching ahead for when
+    /// they pass nearest, and jumping there.
+    fn approach_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.approach_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut jump = None;
+        let now = self.simulation.elapsed_seconds();
+        let julian_date = |elapsed_seconds| self.epoch + elapsed_seconds / SECONDS_PER_DAY;
+        let bodies: Vec<_> = self
+            .simulation
+            .bodies()
+            .iter()
+            .map(|body| (body.id, body.name.clone()))
+            .collect();
+        Window::new("Closest approach")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("approach").show(ui, |ui| {
+                    for (label, pick) in ["Between:", "And:"].into_iter().zip(&mut dialog.bodies) {
+                        ui.label(label);
+                        let selected = bodies
+                            .iter()
+                            .find(|&&(id, _)| Some(id) == *pick)
+                            .map_or("—", |(_, name)| name);
+                        egui::ComboBox::from_id_salt(label)
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for (id, name) in &bodies {
+                                    ui.selectable_value(pick, Some(*id), name);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                    ui.label("Within:");
+                    let mut days = dialog.horizon_s / SECONDS_PER_DAY;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut days)
+                                .range(0.0..=1e6)
+                                .speed(1.)
+                                .suffix(" days"),
+                        )
+                        .changed()
+                    {
+                        dialog.horizon_s = days * SECONDS_PER_DAY;
+                    }
+                    ui.end_row();
+                });
+                match dialog.search.as_ref().map(ApproachSearch::elapsed_seconds) {
+                    Some(searched) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!(
+                                "Searched {} ahead",
+                                format_duration((searched - now).max(0.) as f32)
+                            ));
+                            if ui.button("Stop").clicked() {
+                                dialog.search = None;
+                            }
+                        });
+                    }
+                    None => {
+                        let pair = match dialog.bodies {
+                            [Some(a), Some(b)] if a != b => Some([a, b]),
+                            _ => None,
+                        };
+                        if ui
+                            .add_enabled(pair.is_some(), egui::Button::new("Find"))
+                            .on_hover_text(
+                                "Runs a copy of the simulation ahead, leaving this one as it is",
+                            )
+                            .clicked()
+                        {
+                            if let Some(pair) = pair {
+                                dialog.search = Some(ApproachSearch::new(
+                                    &self.simulation,
+                                    pair,
+                                    dialog.horizon_s,
+                                ));
+                                dialog.outcome = None;
+                            }
+                        }
+                    }
+                }
+                let Some((pair, outcome)) = dialog.outcome else {
+                    return;
+                };
+                let name = |id| {
+                    bodies
+                        .iter()
+                        .find(|&&(other, _)| other == id)
+                        .map_or("—", |(_, name)| name.as_str())
+                };
+                let (a, b) = (name(pair[0]), name(pair[1]));
+                match outcome {
+                    ApproachOutcome::Found(approach) => {
+                        let units = self.units;
+                        ui.label(format!(
+                            "{} {a} and {b} on {}, {} from now, {} apart at {}.",
+                            if approach.collision {
+                                "Collision of"
+                            } else {
+                                "Closest approach of"
+                            },
+                            DateTime::from_julian_date(julian_date(approach.elapsed_seconds)),
+                            format_duration((approach.elapsed_seconds - now).abs() as f32),
+                            units.distance(approach.distance_m),
+                            units.speed(approach.speed_m_s),
+                        ));
+                        if ui
+                            .button("Jump to it")
+                            .on_hover_text("Runs the simulation there and pauses")
+                            .clicked()
+                        {
+                            jump = Some((pair, approach.elapsed_seconds));
+                        }
+                    }
+                    ApproachOutcome::TimedOut => {
+                        ui.label(format!(
+                            "{a} and {b} don't pass nearest within {}.",
+                            format_duration(dialog.horizon_s as f32)
+                        ));
+                    }
+                    ApproachOutcome::Lost => {
+                        ui.label(format!("{a} or {b} merges with another body first."));
+                    }
+                }
+            });
+        if let Some((pair, elapsed_seconds)) = jump {
+            self.record_history();
+            self.seek_to = Some(elapsed_seconds);
+            self.paused = true;
+            self.selection = pair.to_vec();
+        }
+        if !open {
+            self.approach_dialog = None;
+        }
+    }
+
+    /// Shows the "New body from elements" dialog, if open, adding its body when asked.
+    fn elements_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.elements_dialog else {
+            return;
+        };
+        let units = self.units;
+        let mut open = true;
+        let mut add = false;
+        Window::new("New body from elements")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let ElementsDialog {
+                    name,
+                    mass_earths,
+                    color,
+                    primary,
+                    elements,
+                } = dialog;
+                Grid::new("elements").show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(name);
+                    ui.end_row();
+                    ui.label("Mass:");
+                    ui.add(
+                        egui::DragValue::new(mass_earths)
+                            .range(1e-6..=1e6)
+                            .speed(0.1)
+                            .suffix(" x Earth"),
+                    );
+                    ui.end_row();
+                    ui.label("Color:");
+                    ui.color_edit_button_srgba(color);
+                    ui.end_row();
+                    ui.label("Primary:");
+                    let selected_text = self
+                        .simulation
+                        .body(*primary)
+                        .map_or_else(String::new, |primary| primary.name.clone());
+                    egui::ComboBox::from_id_salt("elements_primary")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for body in self.simulation.bodies() {
+                                if ui
+                                    .selectable_label(*primary == body.id, body.name.as_str())
+                                    .clicked()
+                                {
+                                    *primary = body.id;
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    let (meters_per_unit, distance_suffix) = units.distance_unit();
+                    let mut semi_major_axis = elements.semi_major_axis_m / meters_per_unit;
+                    let speed = semi_major_axis * 0.01;
+                    ui.label("Semi-major axis:");
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut semi_major_axis)
+                                .range(f64::MIN_POSITIVE..=f64::MAX)
+                                .speed(speed)
+                                .custom_formatter(|value, _| format_number(value))
+                                .suffix(format!(" {distance_suffix}")),
+                        )
+                        .changed()
+                    {
+                        elements.semi_major_axis_m = semi_major_axis * meters_per_unit;
+                    }
+                    ui.end_row();
+                    ui.label("Eccentricity:");
+                    ui.add(
+                        egui::DragValue::new(&mut elements.eccentricity)
+                            .range(0.0..=f64::MAX)
+                            .speed(0.001)
+                            .max_decimals(4),
+                    );
+                    ui.end_row();
+                    let angle = |ui: &mut Ui, label: &str, degrees: &mut f64| {
+                        ui.label(label);
+                        ui.add(
+                            egui::DragValue::new(degrees)
+                                .range(0.0..=360.)
+                                .speed(0.5)
+                                .suffix("°"),
+                        );
+                        ui.end_row();
+                    };
+                    angle(
+                        ui,
+                        "Argument of periapsis:",
+                        &mut elements.argument_of_periapsis_deg,
+                    );
+                    angle(ui, "True anomaly:", &mut elements.true_anomaly_deg);
+                    ui.label("Inclination:");
+                    ui.add(
+                        egui::DragValue::new(&mut elements.inclination_deg)
+                            .range(-90.0..=90.)
+                            .speed(0.1)
+                            .suffix("°"),
+                    )
+                    .on_hover_text("Only tilts how the orbit is drawn");
+                    ui.end_row();
+                });
+                let bound = elements.eccentricity < 1.;
+                if !bound {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "An eccentricity of 1 or more isn't a bound orbit.",
+                    );
+                }
+                add = ui
+                    .add_enabled(
+                        bound && self.simulation.body(*primary).is_some(),
+                        egui::Button::new("Add body"),
+                    )
+                    .clicked();
+            });
+        if add {
+            if let Some(primary) = self.simulation.body(dialog.primary).cloned() {
+                self.history.record(&self.simulation, &self.selection);
+                // Assume an Earth-like density.
+                let radius_km = 6_371. * dialog.mass_earths.cbrt() as f32;
+                let body = Body::from_elements(
+                    &primary,
+                    &dialog.name,
+                    dialog.mass_earths * EARTH_MASS_KG,
+                    radius_km,
+                    dialog.color,
+                    &dialog.elements,
+                );
+                self.selection = vec![body.id];
+                self.simulation.add(body);
+            }
+        }
+        if add || !open {
+            self.elements_dialog = None;
+        }
+    }
+
+    /// Draws the whole system into `rect`, outlining the part of it shown in a plot of
+    /// `plot_size`, and recenters the view wherever the minimap is clicked or dragged.
+    fn minimap(
+        &mut self,
+        ui: &mut Ui,
+        rect: Rect,
+        plot_size: Vec2,
+        colors: &[Color32],
+        palette: &Palette,
+    ) {
+        let Some(view) = &mut self.view else {
+            return;
+        };
+        let overview = View::fit(self.simulation.bodies(), self.projection, rect.size());
+        let to_screen = |plot_position: Vec2| {
+            let offset = (plot_position - overview.center) / overview.scale;
+            rect.center() + vec2(offset.x, -offset.y)
+        };
+        let painter = ui.painter_at(rect);
+        painter.rect(
+            rect,
+            4.,
+            ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+            ui.visuals().window_stroke,
+        );
+        for (body, &color) in self.simulation.bodies().iter().zip(colors) {
+            let center = to_screen(self.projection.body(body));
+            painter.circle_filled(center, 1.5, color);
+        }
+        let bounds = view.bounds(plot_size);
+        let [min, max] =
+            [bounds.min(), bounds.max()].map(|[x, y]| to_screen(vec2(x as f32, y as f32)));
+        // Kept big enough to find when zoomed far in.
+        let outline = Rect::from_two_pos(min, max);
+        let outline = Rect::from_center_size(outline.center(), outline.size().max(Vec2::splat(4.)));
+        painter.rect_stroke(outline, 0., Stroke::new(1., palette.highlight));
+        let response = ui.interact(rect, Id::new("minimap"), Sense::click_and_drag());
+        if let Some(pointer) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked() || response.dragged())
+        {
+            let offset = (pointer - rect.center()) * overview.scale;
+            view.center = overview.center + vec2(offset.x, -offset.y);
+            self.view_velocity.pan = Vec2::ZERO;
+            self.follow = false;
+            self.follow_barycenter = false;
+        }
+    }
+
+    fn body_colors(&self) -> (Vec<Color32>, Option<[f64; 2]>) {
+        let bodies = self.simulation.bodies();
+        let values = bodies
+            .iter()
+            .max_by(|a, b| a.mass_kg.total_cmp(&b.mass_kg))
+            .and_then(|heaviest| {
+                bodies
+                    .iter()
+                    .map(|body| self.color_by.value(body, heaviest))
+                    .collect::<Option<Vec<_>>>()
+            });
+        let Some(values) = values else {
+            return (bodies.iter().map(|body| body.color).collect(), None);
+        };
+        let range = values
+            .iter()
+            .fold([f64::INFINITY, f64::NEG_INFINITY], |[min, max], &value| {
+                [min.min(value), max.max(value)]
+            });
+        let colors = values
+            .iter()
+            .map(|&value| colormap(self.color_by.normalize(value, range)))
+            .collect();
+        (colors, Some(range))
+    }
+
+    /// Moves the selection onto whatever bodies absorbed the selected ones.
+    fn follow_mergers(&mut self, mergers: Vec<Merger>) {
+        for merger in mergers {
+            self.frame.follow_merger(&merger);
+            let mut absorbed = false;
+            self.selection.retain(|&selected| {
+                let original = merger
+                    .originals
+                    .iter()
+                    .any(|original| original.id == selected);
+                absorbed |= original;
+                !original
+            });
+            if absorbed {
+                self.selection.push(merger.merged);
+            }
+        }
+    }
+
+    fn delete(&mut self, bodies: &[BodyId]) {
+        if bodies.is_empty() {
+            return;
+        }
+        self.record_history();
+        for &body in bodies {
+            self.simulation.remove(body);
+            self.selection.retain(|&selected| selected != body);
+        }
+        self.follow = false;
+    }
+
+    /// 1 while time runs forwards, or -1 while it runs backwards.
+    fn time_direction(&self) -> f64 {
+        if self.reversed {
+            -1.
+        } else {
+            1.
+        }
+    }
+
+    /// How far to advance the simulation for a frame drawn at `now` after one at `last`, in
+    /// seconds of real time each, at `scale` simulated seconds per real second.
+    fn frame_step(last: f64, now: f64, scale: f64) -> f64 {
+        (now - last).min(Self::MAX_FRAME_DT) * scale
+    }
+
+    /// Saves the state before an edit, so that it can be undone.
+    fn record_history(&mut self) {
+        self.history.record(&self.simulation, &self.selection);
+    }
+
+    fn undo(&mut self) {
+        if self.history.undo(&mut self.simulation, &mut self.selection) {
+            self.conservation_history.clear();
+            self.alignments.reset();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.history.redo(&mut self.simulation, &mut self.selection) {
+            self.conservation_history.clear();
+            self.alignments.reset();
+        }
+    }
+
+    /// Runs the simulation on by the time since the last frame, or towards the date being gone
+    /// to, and brings up to date what depends on it: the frame, the search for a closest
+    /// approach and the alignments watched for.
+    fn advance(&mut self, ctx: &egui::Context, now: f64, exporting: bool, focused: bool) {
+        if self
+            .cr3bp
+            .is_some_and(|problem| !problem.is_alive(&self.simulation))
+        {
+            self.cr3bp = None;
+        }
+        if let Some(seek_to) = self.seek_to.filter(|_| !exporting) {
+            let deadline = Instant::now() + Self::PHYSICS_BUDGET;
+            let mergers = self.simulation.run_for_while(
+                seek_to - self.simulation.elapsed_seconds(),
+                self.integrator,
+                || Instant::now() < deadline,
+            );
+            self.follow_mergers(mergers);
+            // Within a millisecond is there, whatever rounding in the steps left over.
+            if (seek_to - self.simulation.elapsed_seconds()).abs() < 1e-3 {
+                self.simulation.set_elapsed_seconds(seek_to);
+                self.seek_to = None;
+            }
+            ctx.request_repaint();
+        } else if let Some(last_update) = self
+            .last_update
+            .filter(|_| !self.paused && !exporting && focused)
+        {
+            let deadline = Instant::now() + Self::PHYSICS_BUDGET;
+            let dt = Self::frame_step(
+                last_update,
+                now,
+                self.time_direction() * self.time_scale as f64,
+            );
+            match self.cr3bp {
+                Some(problem) => {
+                    problem.run_for_while(&mut self.simulation, dt, || Instant::now() < deadline)
+                }
+                None => {
+                    let mergers = self
+                        .simulation
+                        .advance_while(dt, self.integrator, || Instant::now() < deadline);
+                    self.follow_mergers(mergers);
+                }
+            }
+        }
+        // Paused, the bodies are shown exactly where they are, to line up with their editing. The
+        // restricted three-body problem's steps carry nothing over, so leave no lag either.
+        self.projection.step_lag = if self.paused || self.cr3bp.is_some() {
+            0.
+        } else {
+            self.simulation.step_lag()
+        };
+        if !self.frame.is_alive(&self.simulation) {
+            self.frame = Frame::Inertial;
+        }
+        self.projection.frame = self
+            .frame
+            .transform(&self.simulation, self.projection.step_lag)
+            .unwrap_or_default();
+        self.last_update = focused.then_some(now);
+        self.receive_screenshot(ctx);
+        if !exporting {
+            self.search_approach(ctx);
+        }
+        if !self.alignments.alignments().is_empty() {
+            let events = self.alignments.check(&self.simulation);
+            self.log_events(events);
+        }
+    }
+
+    /// Acts on the keyboard shortcuts pressed, unless a text field has the keyboard.
+    fn keyboard_shortcuts(&mut self, ctx: &egui::Context) {
+        let shortcut_with = |modifiers, key| {
+            !ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_key(modifiers, key))
+        };
+        let shortcut = |key| shortcut_with(Modifiers::NONE, key);
+        if shortcut(Key::F) {
+            self.follow = !self.follow;
+        }
+        if shortcut(Key::Delete) {
+            self.delete(&self.selection.clone());
+        }
+        // `consume_key` ignores an extra Shift, so check for Ctrl+Shift+Z before Ctrl+Z.
+        if shortcut_with(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+            || shortcut_with(Modifiers::COMMAND, Key::Y)
+        {
+            self.redo();
+        } else if shortcut_with(Modifiers::COMMAND, Key::Z) {
+            self.undo();
+        }
+        if shortcut(Key::Escape) {
+            self.measuring = false;
+            self.measurement = Default::default();
+        }
+        if shortcut_with(Modifiers::SHIFT, Key::Tab) {
+            self.cycle_selection(-1);
+        } else if shortcut(Key::Tab) {
+            self.cycle_selection(1);
+        }
+    }
+
+    /// The menus along the top, and the scenario, integrator and frame picked beside them.
+    fn menu_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| self.file_menu(ui));
+                ui.menu_button("Edit", |ui| self.edit_menu(ui));
+                ui.menu_button("View", |ui| self.view_menu(ui));
+                if ui
+                    .selectable_label(self.placement.is_some(), "Add body")
+                    .clicked()
+                {
+                    self.placement = match self.placement {
+                        Some(_) => None,
+                        None => Some(Default::default()),
+                    };
+                    self.measuring = false;
+                }
+                if ui.selectable_label(self.measuring, "Measure").clicked() {
+                    self.measuring = !self.measuring;
+                    self.placement = None;
+                }
+                ui.menu_button("Simulation", |ui| self.simulation_menu(ui));
+                let scenario_before = self.scenario;
+                egui::ComboBox::from_label("Scenario")
+                    .selected_text(self.scenario.name())
+                    .show_ui(ui, |ui| {
+                        for kind in Scenario::ALL {
+                            ui.selectable_value(&mut self.scenario, kind, kind.name());
+                        }
+                    });
+                if self.scenario != scenario_before {
+                    self.load(Simulation::new(scenario(self.scenario)));
+                }
+                egui::ComboBox::from_label("Integrator")
+                    .selected_text(self.integrator.name())
+                    .show_ui(ui, |ui| {
+                        for integrator in Integrator::ALL {
+                            ui.selectable_value(
+                                &mut self.integrator,
+                                integrator,
+                                integrator.name(),
+                            );
+                        }
+                    });
+                let frame_before = self.frame;
+                egui::ComboBox::from_label("Frame")
+                    .selected_text(self.frame.name(&self.simulation))
+                    .show_ui(ui, |ui| {
+                        let bodies = self.simulation.bodies();
+                        let mut frames = vec![Frame::Inertial, Frame::Barycentric];
+                        let massive = bodies.iter().filter(|body| body.gravitates);
+                        frames.extend(massive.map(|body| Frame::Centered(body.id)));
+                        frames.extend(
+                            self.simulation
+                                .primaries()
+                                .into_iter()
+                                .enumerate()
+                                .filter_map(|(index, primary)| {
+                                    let secondary = &bodies[index];
+                                    Some(Frame::CoRotating([bodies[primary?].id, secondary.id]))
+                                        .filter(|_| secondary.gravitates)
+                                }),
+                        );
+                        for frame in frames {
+                            let name = frame.name(&self.simulation);
+                            ui.selectable_value(&mut self.frame, frame, name);
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "What positions are drawn relative to. A rotating frame turns with a \
+                         body and its primary, so that their Lagrange points stand still.",
+                    );
+                if self.frame != frame_before {
+                    self.view = None;
+                    self.view_velocity = Default::default();
+                }
+            });
+        });
+    }
+
+    /// Opening, saving, importing and exporting.
+    fn file_menu(&mut self, ui: &mut Ui) {
+        if ui.button("Open system…").clicked() {
+            self.file_prompt = Some(FileAction::OpenSystem);
+            ui.close_menu();
+        }
+        if ui.button("Save system as…").clicked() {
+            self.file_prompt = Some(FileAction::SaveSystem);
+            ui.close_menu();
+        }
+        if ui
+            .button("Import Horizons vectors…")
+            .on_hover_text(
+                "Loads real positions and velocities from a JPL Horizons vector \
+                 table, saved in CSV format",
+            )
+            .clicked()
+        {
+            self.file_prompt = Some(FileAction::ImportHorizons);
+            ui.close_menu();
+        }
+        if ui
+            .button("Paste Horizons vectors…")
+            .on_hover_text(
+                "For a vector table copied from the Horizons web app, to load or to \
+                 add its bodies to the system",
+            )
+            .clicked()
+        {
+            self.horizons_paste = Some(String::new());
+            ui.close_menu();
+        }
+        if ui
+            .button("Add satellites from TLEs…")
+            .on_hover_text("For two-line element sets of Earth satellites, as from CelesTrak")
+            .clicked()
+        {
+            self.tle_dialog = Some(Default::default());
+            ui.close_menu();
+        }
+        // There's no file system to write to on the web.
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Export trajectories…")
+            .on_hover_text(
+                "Runs a copy of the system ahead, recording the selected bodies' \
+                 states as CSV",
+            )
+            .clicked()
+        {
+            self.trajectory_export = Some(YEAR_S as f64);
+            ui.close_menu();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui.button("Export image…").clicked() {
+            self.file_prompt = Some(FileAction::ExportImage);
+            ui.close_menu();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui.button("Record animation…").clicked() {
+            self.animation_dialog = true;
+            ui.close_menu();
+        }
+    }
+
+    /// Undo, redo, and adding a body from its orbital elements.
+    fn edit_menu(&mut self, ui: &mut Ui) {
+        let undo = egui::Button::new("Undo").shortcut_text("Ctrl+Z");
+        if ui.add_enabled(self.history.can_undo(), undo).clicked() {
+            self.undo();
+            ui.close_menu();
+        }
+        let redo = egui::Button::new("Redo").shortcut_text("Ctrl+Y");
+        if ui.add_enabled(self.history.can_redo(), redo).clicked() {
+            self.redo();
+            ui.close_menu();
+        }
+        ui.separator();
+        // Around the selected body by default, or else the heaviest.
+        let primary = self.selected().or_else(|| {
+            self.simulation
+                .bodies()
+                .iter()
+                .max_by(|a, b| a.mass_kg.total_cmp(&b.mass_kg))
+        });
+        if ui
+            .add_enabled(
+                primary.is_some(),
+                egui::Button::new("New body from elements…"),
+            )
+            .clicked()
+        {
+            if let Some(primary) = primary {
+                let name = format!("Body {}", self.simulation.bodies().len() + 1);
+                self.elements_dialog = Some(ElementsDialog::new(primary, name));
+            }
+            ui.close_menu();
+        }
+    }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-#[serde(default)] // if we add new fields, give them default values when deserializing old state
-pub struct App {
-    #[serde(skip)]
-    view: Option<View>,
-    #[serde(skip)]
-    bodies: Vec<Rc<Body>>,
-    selected: Weak<Body>,
-}
+    /// How the system is drawn.
+    fn view_menu(&mut self, ui: &mut Ui) {
+        if ui.button("Fit all bodies").clicked() {
+            self.view = None;
+            self.view_velocity = Default::default();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(self.earth().is_some(), egui::Button::new("Earth orbit"))
+            .on_hover_text(
+                "Centers the frame on Earth, out to geostationary orbit, with at \
+                 most a minute's timestep",
+            )
+            .clicked()
+        {
+            self.earth_orbit_view(ui.ctx().screen_rect().size());
+            ui.close_menu();
+        }
+        if ui
+            .checkbox(&mut self.projection.log_distances, "Logarithmic distances")
+            .changed()
+        {
+            self.view = None;
+            self.view_velocity = Default::default();
+        }
+        ui.add(
+            egui::Slider::new(
+                &mut self.projection.tilt_deg,
+                0.0..=Projection::MAX_TILT_DEG,
+            )
+            .suffix("°")
+            .text("Tilt"),
+        )
+        .on_hover_text(
+            "Views the system at an angle, so that inclined orbits stand out of its \
+             plane. Only the drawing is three-dimensional.",
+        );
+        ui.menu_button("Body size", |ui| {
+            for size in BodySize::ALL {
+                ui.radio_value(&mut self.body_size, size, size.name());
+            }
+            ui.add_enabled(
+                self.body_size == BodySize::ToScale,
+                egui::Slider::new(&mut self.size_exaggeration, 1.0..=10_000.)
+                    .logarithmic(true)
+                    .suffix("×")
+                    .text("Exaggeration"),
+            );
+        });
+        ui.checkbox(&mut self.show_labels, "Labels");
+        ui.checkbox(&mut self.show_reference_circles, "Reference circles");
+        ui.add_enabled(
+            !self.projection.log_distances,
+            egui::Checkbox::new(&mut self.show_grid, "Grid"),
+        )
+        .on_disabled_hover_text("Logarithmic distances have no single scale");
+        ui.checkbox(&mut self.inertia, "Inertia");
+        ui.checkbox(&mut self.follow, "Follow selected (F)");
+        ui.menu_button("Theme", |ui| {
+            for (theme, name) in [(Theme::Dark, "Dark"), (Theme::Light, "Light")] {
+                if ui.radio_value(&mut self.theme, theme, name).changed() {
+                    ui.ctx().set_theme(theme);
+                }
+            }
+        });
+        ui.menu_button("Units", |ui| {
+            for units in UnitSystem::ALL {
+                ui.radio_value(&mut self.units, units, units.name());
+            }
+        });
+        ui.checkbox(&mut self.show_diagnostics, "Diagnostics");
+        ui.checkbox(&mut self.show_chart, "Chart");
+        ui.checkbox(&mut self.show_events, "Events");
+        ui.checkbox(&mut self.show_barycenter, "Show barycenter");
+        ui.checkbox(&mut self.show_lagrange_points, "Lagrange points");
+        ui.checkbox(&mut self.show_minimap, "Minimap");
+        ui.checkbox(&mut self.follow_barycenter, "Center on barycenter");
+        ui.menu_button("Color by", |ui| {
+            for color_by in ColorBy::ALL {
+                ui.radio_value(&mut self.color_by, color_by, color_by.name());
+            }
+        });
+        ui.menu_button("Velocity arrows", |ui| {
+            for arrows in VelocityArrows::ALL {
+                ui.radio_value(&mut self.velocity_arrows, arrows, arrows.name());
+            }
+            ui.add_enabled(
+                self.velocity_arrows != VelocityArrows::Off,
+                egui::Slider::new(&mut self.arrow_scale, 0.1..=100.)
+                    .logarithmic(true)
+                    .suffix(" pt per km/s")
+                    .text("Length"),
+            );
+        });
+        ui.add(
+            egui::Slider::new(
+                &mut self.simulation.trail_duration_s,
+                0.0..=100. * YEAR_S as f64,
+            )
+            .logarithmic(true)
+            .smallest_positive(DAY_S as f64)
+            .custom_formatter(|seconds, _| format_duration(seconds as f32))
+            .text("Trail length"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.trail_fade, 0.0..=1.)
+                .custom_formatter(|fraction, _| format!("{:.0}%", fraction * 100.))
+                .text("Trail fade"),
+        )
+        .on_hover_text("How much of each trail fades out, from its oldest end");
+        ui.add(
+            egui::Slider::new(&mut self.prediction_steps, 0..=10_000)
+                .custom_formatter(|steps, _| {
+                    format_duration((steps * self.simulation.timestep_s) as f32)
+                })
+                .text("Predicted path"),
+        )
+        .on_hover_text("How far ahead to forecast the selected body's path");
+    }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
-struct View {
-    center: Vec2,
-    scale: f32,
-}
+    /// The epoch, the scenario's bodies and how the physics is computed.
+    fn simulation_menu(&mut self, ui: &mut Ui) {
+        ui.label("Epoch:");
+        let mut date = DateTime::from_julian_date(self.epoch);
+        let changed = ui
+            .horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut date.year)).changed()
+                    | ui.add(egui::DragValue::new(&mut date.month).range(1..=12))
+                        .changed()
+                    | ui.add(egui::DragValue::new(&mut date.day).range(1..=31))
+                        .changed()
+            })
+            .inner;
+        if changed {
+            self.epoch = date.julian_date();
+        }
+        if ui.button("Reset to J2000").clicked() {
+            self.epoch = J2000;
+        }
+        if ui.button("Go to date…").clicked() {
+            let elapsed_days = self.simulation.elapsed_seconds() / SECONDS_PER_DAY;
+            self.date_dialog = Some(DateTime::from_julian_date(self.epoch + elapsed_days));
+            ui.close_menu();
+        }
+        if ui.button("Restricted three-body problem…").clicked() {
+            self.show_cr3bp = true;
+            ui.close_menu();
+        }
+        if ui.button("Find closest approach…").clicked() {
+            let mut bodies = [None; 2];
+            for (pick, id) in bodies.iter_mut().zip(&self.selection) {
+                *pick = Some(*id);
+            }
+            self.approach_dialog = Some(ApproachDialog {
+                bodies,
+                horizon_s: YEAR_S as f64,
+                search: None,
+                outcome: None,
+            });
+            ui.close_menu();
+        }
+        if ui
+            .button("New solar system on the epoch")
+            .on_hover_text(
+                "Puts the planets where they are on the epoch's date, from JPL's \
+                 approximate orbital elements",
+            )
+            .clicked()
+        {
+            self.scenario = Scenario::SolarSystem;
+            self.load(Simulation::new(solar_system_on(self.epoch)));
+            ui.close_menu();
+        }
+        ui.separator();
+        ui.label("Asteroid belt:");
+        Grid::new("belt").show(ui, |ui| {
+            ui.label("Count:");
+            ui.add(egui::DragValue::new(&mut self.belt_count).range(1..=10_000));
+            ui.end_row();
+            ui.label("Seed:");
+            // `DragValue` goes through `f64`, which can't hold every `u64`.
+            ui.add(egui::DragValue::new(&mut self.belt_seed).range(0..=u32::MAX));
+            ui.end_row();
+        });
+        ui.checkbox(&mut self.belt_gravitates, "Asteroids gravitate")
+            .on_hover_text(
+                "Untick to add the asteroids as massless test particles, which are \
+                 much faster to simulate in large numbers",
+            );
+        if ui.button("Add belt").clicked() {
+            self.record_history();
+            for body in generate_belt(
+                self.belt_seed,
+                self.belt_count,
+                BELT_INNER_KM,
+                BELT_OUTER_KM,
+            ) {
+                self.simulation.add(body.gravitating(self.belt_gravitates));
+            }
+        }
+        ui.separator();
+        if ui.button("Restart scenario").clicked() {
+            self.load(Simulation::new(scenario(self.scenario)));
+            ui.close_menu();
+        }
+        ui.checkbox(&mut self.pause_when_unfocused, "Pause in background");
+        ui.separator();
+        egui::ComboBox::from_label("Collisions")
+            .selected_text(self.simulation.collisions.name())
+            .show_ui(ui, |ui| {
+                for collisions in Collisions::ALL {
+                    ui.selectable_value(
+                        &mut self.simulation.collisions,
+                        collisions,
+                        collisions.name(),
+                    );
+                }
+            });
+        ui.checkbox(&mut self.simulation.relativity, "General relativity")
+            .on_hover_text(
+                "Adds the post-Newtonian correction that makes Mercury's \
+                 perihelion advance. Slows the simulation down.",
+            );
+        let mut softening_km = self.simulation.softening_m / 1e3;
+        if ui
+            .add(
+                egui::Slider::new(&mut softening_km, 0.0..=1e7)
+                    .logarithmic(true)
+                    .suffix(" km")
+                    .text("Softening length"),
+            )
+            .on_hover_text(
+                "Weakens gravity between bodies closer than about this distance, \
+                so that near misses stay stable. Zero gives plain Newtonian gravity.",
+            )
+            .changed()
+        {
+            self.simulation.softening_m = softening_km * 1e3;
+        }
+        let in_use = if self.simulation.uses_barnes_hut() {
+            " (in use)"
+        } else {
+            ""
+        };
+        ui.checkbox(
+            &mut self.simulation.barnes_hut,
+            format!("Barnes–Hut approximation{in_use}"),
+        )
+        .on_hover_text(
+            "Approximates the gravity of distant groups of bodies by their \
+             center of mass. Much faster with thousands of bodies, but less exact.",
+        );
+        ui.add_enabled_ui(self.simulation.barnes_hut, |ui| {
+            ui.add(
+                egui::Slider::new(&mut self.simulation.barnes_hut_min_bodies, 1..=100_000)
+                    .logarithmic(true)
+                    .text("From bodies"),
+            )
+            .on_hover_text("Fewer bodies than this are summed over exactly");
+            ui.add(
+                egui::Slider::new(&mut self.simulation.opening_angle, 0.1..=1.5)
+                    .text("Opening angle"),
+            )
+            .on_hover_text("Smaller is more accurate and slower");
+        });
+        ui.separator();
+        ui.add_enabled(
+            !self.simulation.adaptive_timestep,
+            egui::Slider::new(&mut self.simulation.timestep_s, 1.0..=10. * DAY_S as f64)
+                .logarithmic(true)
+                .custom_formatter(|seconds, _| format_duration(seconds as f32))
+                .text("Step"),
+        )
+        .on_hover_text(
+            "Simulated time per physics step. Shorter steps are more accurate and \
+             slower; bodies are drawn smoothly between steps either way.",
+        );
+        ui.checkbox(&mut self.simulation.adaptive_timestep, "Adaptive timestep")
+            .on_hover_text(
+                "Takes shorter steps while bodies pass close to each other, and \
+                 longer ones while they don't.",
+            );
+        ui.add_enabled_ui(self.simulation.adaptive_timestep, |ui| {
+            let simulation = &mut self.simulation;
+            fn timestep_slider(seconds: &mut f64) -> egui::Slider<'_> {
+                egui::Slider::new(seconds, 1.0..=10. * DAY_S as f64)
+                    .logarithmic(true)
+                    .custom_formatter(|seconds, _| format_duration(seconds as f32))
+            }
+            if ui
+                .add(timestep_slider(&mut simulation.min_timestep_s).text("Shortest step"))
+                .changed()
+            {
+                simulation.max_timestep_s =
+                    simulation.max_timestep_s.max(simulation.min_timestep_s);
+            }
+            if ui
+                .add(timestep_slider(&mut simulation.max_timestep_s).text("Longest step"))
+                .changed()
+            {
+                simulation.min_timestep_s =
+                    simulation.min_timestep_s.min(simulation.max_timestep_s);
+            }
+        });
+    }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-struct Body {
-    name: String,
-    mass_kg: f32,
-    position: Vec2,
-    color: Color32,
-    velocity: Vec2,
-}
+    /// The bar along the bottom: playing, pausing, reversing and stepping, the time scale, and the
+    /// drift, if shown.
+    fn time_controls(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("time_controls").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if let Some(recording) = &self.recording {
+                    let stop = ui
+                        .button(format!(
+                            "⏹ Stop recording ({} of {})",
+                            recording.captured, recording.settings.frames
+                        ))
+                        .clicked();
+                    if stop || ui.input(|i| i.key_pressed(Key::Escape)) {
+                        self.finish_recording();
+                    }
+                    ui.separator();
+                }
+                let label = if self.paused { "▶ Play" } else { "⏸ Pause" };
+                if ui.button(label).clicked() {
+                    self.paused = !self.paused;
+                }
+                ui.toggle_value(&mut self.reversed, "◀ Reverse")
+                    .on_hover_text(
+                        "Runs time backwards, winding trails back in as the bodies retrace them. \
+                     Velocity Verlet, leapfrog and Runge-Kutta 4 retrace the bodies' paths \
+                     closely, but the Euler methods drift away from them, and merged bodies stay \
+                     merged.",
+                    );
+                if ui
+                    .add_enabled(self.paused, egui::Button::new("Step"))
+                    .clicked()
+                {
+                    let mergers = self.simulation.step(
+                        self.time_direction() * self.simulation.timestep_s,
+                        self.integrator,
+                    );
+                    self.follow_mergers(mergers);
+                }
+                ui.add(
+                    egui::Slider::new(
+                        &mut self.time_scale,
+                        Self::MIN_TIME_SCALE..=Self::MAX_TIME_SCALE,
+                    )
+                    .logarithmic(true)
+                    .custom_formatter(|seconds, _| {
+                        if seconds <= Self::MIN_TIME_SCALE as f64 {
+                            "Real time".to_string()
+                        } else {
+                            format!("{}/s", format_duration(seconds as f32))
+                        }
+                    })
+                    .text("Time scale"),
+                );
+                let drift = self
+                    .conservation_history
+                    .front()
+                    .zip(self.conservation_history.back())
+                    .filter(|_| self.show_drift)
+                    .map(|(first, last)| last.drift_since(first));
+                if let Some([energy, momentum, angular_momentum]) = drift {
+                    ui.separator();
+                    ui.label(RichText::new(format!("ΔE {:+.2e}%", energy * 100.)).monospace())
+                        .on_hover_text(format!(
+                            "Drift since tracking started, relative to the starting values: \
+                             energy {:+.2e}%, momentum {:.2e}%, angular momentum {:+.2e}%",
+                            energy * 100.,
+                            momentum * 100.,
+                            angular_momentum * 100.,
+                        ));
+                }
+            });
+        });
+    }
 
-impl Body {
-    fn orbiting(
-        name: &str,
-        mass_kg: f32,
-        orbital_radius_km: f32,
-        color: Color32,
-        degrees: f32,
-    ) -> Rc<Self> {
-        let radius = orbital_radius_km * 1e3;
-        let radians = degrees.to_radians();
-        Rc::new(Self {
-            name: name.to_string(),
-            mass_kg,
-            position: vec2(radius * radians.cos(), radius * radians.sin()),
-            color,
-            velocity: Vec2::ZERO,
-        })
+    /// The plot of the system filling the window, with everything drawn over it, handling clicks
+    /// on it. Returns whether something on it is flashing, to keep redrawing while it does.
+    fn central_panel(&mut self, ctx: &egui::Context, exporting: bool) -> bool {
+        egui::CentralPanel::default()
+            .frame(egui::containers::Frame::default().inner_margin(Margin::ZERO))
+            .show(ctx, |ui| {
+                let minimap =
+                    Some(minimap_rect(ui.max_rect())).filter(|_| self.show_minimap && !exporting);
+                let outside_minimap =
+                    |pos: &Pos2| !minimap.is_some_and(|minimap| minimap.contains(*pos));
+                let click = ui
+                    .get_click()
+                    .filter(|_| self.placement.is_none())
+                    .filter(outside_minimap);
+                let double_click = ui
+                    .get_double_click()
+                    .filter(|_| self.placement.is_none() && !self.measuring)
+                    .filter(outside_minimap);
+                let barycenter = self.simulation.barycenter();
+                self.update_prediction();
+                let (colors, color_range) = self.body_colors();
+                let (plot, markers) = self.plot(ui, &colors, barycenter);
+                self.add_placed_body(plot.inner);
+                self.plot_guides(ui, &plot.transform, barycenter);
+                // The body nearest the pointer, if the pointer is over its marker.
+                let hovered = plot
+                    .response
+                    .hover_pos()
+                    .filter(|_| !exporting)
+                    .filter(outside_minimap)
+                    .and_then(|pointer| self.body_under(&plot.transform, markers, pointer));
+                let flashing = self.draw_bodies(ui, &plot.transform, markers, &colors, hovered);
+                self.plot_clicks(
+                    ui,
+                    &plot.response,
+                    &plot.transform,
+                    markers,
+                    click,
+                    double_click,
+                );
+                self.plot_annotations(ui, &plot.response, &plot.transform, color_range, exporting);
+                if let Some(rect) = minimap {
+                    let palette = Palette::new(self.theme);
+                    self.minimap(ui, rect, plot.response.rect.size(), &colors, &palette);
+                }
+                if let Some(body) = hovered {
+                    self.body_tooltip(ui, body);
+                }
+                flashing
+            })
+            .inner
+    }
+
+    /// Moves the view, then plots the bodies, their trails and reference circles, and the
+    /// selected body's predicted path. Returns the plot, with the position and velocity of a body
+    /// placed on it if one was, and how large the bodies' markers are at its scale.
+    fn plot(
+        &mut self,
+        ui: &mut Ui,
+        colors: &[Color32],
+        barycenter: DVec2,
+    ) -> (PlotResponse<Option<(DVec2, DVec2)>>, MarkerSizing) {
+        // The scale is the view's once the plot has placed it.
+        let mut markers = MarkerSizing {
+            size: self.body_size,
+            exaggeration: self.size_exaggeration,
+            scale: 1.,
+        };
+        let followed = self.selected().filter(|_| self.follow).map(|body| body.id);
+        // A logarithmic projection has no even spacing to grid.
+        let grid_step = self
+            .view
+            .as_ref()
+            .filter(|_| self.show_grid && !self.projection.log_distances)
+            .map(|view| view.grid_step(self.units));
+        let snap = grid_step.filter(|_| self.snap_to_grid);
+        // The grid is drawn over the tilted plane: plot y is foreshortened.
+        let y_per_meter = self.projection.tilt_deg.to_radians().cos() as f64;
+        let units = self.units;
+        let plot = Plot::new("main_plot")
+            .show_grid(grid_step.is_some())
+            .show_axes(grid_step.is_some())
+            .x_grid_spacer(grid_marks(grid_step))
+            .y_grid_spacer(grid_marks(grid_step.map(|step| step * y_per_meter)))
+            .x_axis_formatter(move |mark, _| units.distance(mark.value))
+            .y_axis_formatter(move |mark, _| units.distance(mark.value / y_per_meter))
+            .data_aspect(1.0)
+            .label_formatter(|_, _| "".to_string())
+            .cursor_color(Color32::TRANSPARENT)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .show(ui, |ui| {
+                let response = ui.response().clone();
+                let view = self.view.get_or_insert_with(|| {
+                    View::fit(
+                        self.simulation.bodies(),
+                        self.projection,
+                        response.rect.size(),
+                    )
+                });
+                view.navigate(
+                    &response,
+                    &mut self.view_velocity,
+                    self.inertia,
+                    self.placement.is_none(),
+                );
+                if let Some(body) = followed.and_then(|id| self.simulation.body(id)) {
+                    view.center = self.projection.body(body);
+                    self.view_velocity.pan = Vec2::ZERO;
+                } else if self.follow_barycenter {
+                    view.center = self.projection.apply(barycenter);
+                    self.view_velocity.pan = Vec2::ZERO;
+                }
+                if let Some(export) = &mut self.image_export {
+                    *view = export.tile_view(response.rect.size());
+                    export.rect = response.rect;
+                    if !export.requested {
+                        export.requested = true;
+                        ui.ctx()
+                            .send_viewport_cmd(egui::ViewportCommand::Screenshot(
+                                egui::UserData::new(export.tile),
+                            ));
+                    }
+                }
+                if let Some(recording) = &mut self.recording {
+                    recording.rect = response.rect;
+                    if !recording.requested {
+                        recording.requested = true;
+                        ui.ctx()
+                            .send_viewport_cmd(egui::ViewportCommand::Screenshot(
+                                egui::UserData::new(AnimationFrame(recording.captured)),
+                            ));
+                    }
+                }
+                ui.set_plot_bounds(view.bounds(response.rect.size()));
+                markers.scale = view.scale;
+                let new_body = self.placement.as_mut().and_then(|placement| {
+                    place_body(
+                        ui,
+                        &response,
+                        view,
+                        self.projection,
+                        self.units,
+                        snap,
+                        placement,
+                    )
+                });
+                self.plot_bodies(ui, colors, markers);
+                self.plot_prediction(ui, colors);
+                new_body
+            });
+        (plot, markers)
+    }
+
+    /// Plots each body's marker, its trail, fading from the oldest end, and its reference circle,
+    /// if shown.
+    fn plot_bodies(&self, ui: &mut PlotUi, colors: &[Color32], markers: MarkerSizing) {
+        let primaries = if self.show_reference_circles {
+            self.simulation.primaries()
+        } else {
+            Vec::new()
+        };
+        // Fewer, coarser levels with many bodies, to keep the lines drawn in bounds.
+        let frame_history = self.frame.history(&self.simulation);
+        let fade_levels = (Self::MAX_TRAIL_LINES / self.simulation.bodies().len().max(1))
+            .clamp(1, Self::TRAIL_FADE_LEVELS);
+        for (i, (body, &color)) in self.simulation.bodies().iter().zip(colors).enumerate() {
+            let Body {
+                name,
+                position,
+                trail,
+                inclination_deg,
+                ..
+            } = body;
+            let project = |point| self.projection.apply_inclined(point, *inclination_deg);
+            // Around the primary, where the body would be on a circular orbit.
+            let reference_circle = primaries.get(i).copied().flatten().map(|primary| {
+                let center = self.simulation.bodies()[primary].position;
+                (center, (*position - center).length())
+            });
+            let position = self.projection.body(body);
+            let mut trail: Vec<_> = trail
+                .iter()
+                .filter_map(|&(seconds, point)| {
+                    let transform = frame_history.as_ref()?.at(seconds)?;
+                    Some(
+                        self.projection
+                            .project(transform.apply(point), *inclination_deg),
+                    )
+                })
+                .map(|point| [point.x as f64, point.y as f64])
+                .collect();
+            // It ends where the body is drawn, rather than a step ahead.
+            if let Some(end) = trail.last_mut() {
+                *end = [position.x as f64, position.y as f64];
+            }
+            // Each level of fade is a line of its own, from the most faded at
+            // the oldest end. Consecutive lines share a point so as to join up.
+            let faded = (trail.len() as f32 * self.trail_fade) as usize;
+            let levels = fade_levels.min(faded.max(1));
+            for level in 0..=levels {
+                let start = faded * level / levels;
+                let end = if level == levels {
+                    trail.len()
+                } else {
+                    (faded * (level + 1) / levels + 1).min(trail.len())
+                };
+                let opacity = (level as f32 + 0.5) / levels as f32;
+                ui.add(
+                    Line::new(PlotPoints::new(trail[start..end].to_vec()))
+                        .color(color.gamma_multiply(Self::TRAIL_OPACITY * opacity.min(1.)))
+                        .width(1.),
+                );
+            }
+            ui.add(
+                Points::new(PlotPoints::new(vec![[
+                    position.x as f64,
+                    position.y as f64,
+                ]]))
+                .color(color)
+                .radius(marker_radius(body, markers))
+                .name(name.as_str())
+                .id(Id::new(body.id)),
+            );
+            let Some((center, radius)) = reference_circle else {
+                continue;
+            };
+            ui.add(
+                Line::new(PlotPoints::new(
+                    (0..=360)
+                        .filter(|x| *x % 2 == 0)
+                        .map(|deg| (deg as f64).to_radians())
+                        .map(|rad| project(center + dvec2(rad.cos(), rad.sin()) * radius))
+                        .map(|point| [point.x as f64, point.y as f64])
+                        .collect::<Vec<_>>(),
+                ))
+                .style(LineStyle::Dotted { spacing: 4. })
+                .color(color)
+                .width(0.5),
+            );
+        }
     }
-}
 
-const EARTH_MASS_KG: f32 = 5.97219e24;
+    /// Plots the selected body's predicted path, and its maneuver nodes.
+    fn plot_prediction(&self, ui: &mut PlotUi, colors: &[Color32]) {
+        let selected = self.selected().and_then(|body| {
+            let index = self.simulation.index_of(body.id)?;
+            Some((colors[index], body.inclination_deg))
+        });
+        if let (Some(prediction), Some((color, inclination_deg))) = (&self.prediction, selected) {
+            let project = |point: DVec2| {
+                let point = self.projection.project(point, inclination_deg);
+                [point.x as f64, point.y as f64]
+            };
+            // Past the burn being planned, the path it leads to stands out.
+            let highlight = Palette::new(self.theme).highlight;
+            let split = prediction
+                .draft
+                .map_or(prediction.path.len(), |draft| draft + 1);
+            let (planned, previewed) = prediction.path.split_at(split);
+            ui.add(
+                Line::new(PlotPoints::new(
+                    planned.iter().copied().map(project).collect(),
+                ))
+                .style(LineStyle::Dashed { length: 8. })
+                .color(color)
+                .width(1.5),
+            );
+            if !previewed.is_empty() {
+                ui.add(
+                    Line::new(PlotPoints::new(
+                        planned
+                            .last()
+                            .into_iter()
+                            .chain(previewed)
+                            .copied()
+                            .map(project)
+                            .collect(),
+                    ))
+                    .color(highlight)
+                    .width(1.5),
+                );
+            }
+            ui.add(
+                Points::new(PlotPoints::new(
+                    prediction
+                        .nodes
+                        .iter()
+                        .map(|&node| project(prediction.path[node]))
+                        .collect(),
+                ))
+                .shape(egui_plot::MarkerShape::Diamond)
+                .color(highlight)
+                .radius(5.)
+                .name("Maneuver nodes"),
+            );
+        }
+    }
 
-impl Default for App {
-    fn default() -> Self {
-        Self {
-            bodies: vec![
-                Body::orbiting("Sun", 1.9891e30, 0., Color32::GOLD, 0.),
-                Body::orbiting("Mercury", 3.285e23, 57.9e6, Color32::GRAY, 200.),
-                Body::orbiting("Venus", 4.867e24, 108.2e6, Color32::GREEN, 110.),
-                Body::orbiting("Earth", EARTH_MASS_KG, 1.5e8, Color32::BLUE, 40.),
-                Body::orbiting("Mars", 6.39e23, 228e6, Color32::RED, 40.),
-                Body::orbiting("Jupiter", 1.899e27, 778.5e6, Color32::BROWN, 75.),
-                Body::orbiting("Saturn", 5.683e26, 1.434e9, Color32::YELLOW, 60.),
-                Body::orbiting("Uranus", 8.681e25, 2.871e9, Color32::LIGHT_BLUE, 30.),
-                Body::orbiting("Neptune", 1.024e26, 4.495e9, Color32::BLUE, 15.),
-            ],
-            view: None,
-            selected: Default::default(),
+    /// Adds the body at `placed`, the position and velocity it was placed with, if it was.
+    fn add_placed_body(&mut self, placed: Option<(DVec2, DVec2)>) {
+        if let (Some(placement), Some((position, velocity))) = (&self.placement, placed) {
+            self.history.record(&self.simulation, &self.selection);
+            let name = format!("Body {}", self.simulation.bodies().len() + 1);
+            let mass_kg = placement.mass_earths * EARTH_MASS_KG;
+            // Assume an Earth-like density.
+            let radius_km = 6_371. * placement.mass_earths.cbrt() as f32;
+            self.simulation.add(Body::new(
+                &name,
+                mass_kg,
+                radius_km,
+                position,
+                velocity,
+                placement.color,
+            ));
         }
     }
-}
 
-impl App {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        cc.egui_ctx.set_theme(Theme::Dark);
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+    /// Marks the selected body's Lagrange points, the zero-velocity curves and the barycenter,
+    /// where they're shown.
+    fn plot_guides(&self, ui: &Ui, transform: &PlotTransform, barycenter: DVec2) {
+        let palette = Palette::new(self.theme);
+        if let Some((primary, secondary)) = self
+            .selected()
+            .filter(|_| self.show_lagrange_points)
+            .and_then(|body| {
+                let index = self.simulation.index_of(body.id)?;
+                let primary = self.simulation.primary_of(index)?;
+                Some((&self.simulation.bodies()[primary], body))
+            })
+        {
+            let stroke = Stroke::new(1., palette.lagrange);
+            for (i, point) in lagrange_points(primary, secondary).into_iter().enumerate() {
+                // In the plane of the secondary's orbit.
+                let center = screen_position(
+                    transform,
+                    self.projection
+                        .apply_inclined(point, secondary.inclination_deg),
+                );
+                const SIZE: f32 = 4.;
+                let corners = [
+                    vec2(0., -SIZE),
+                    vec2(SIZE, 0.),
+                    vec2(0., SIZE),
+                    vec2(-SIZE, 0.),
+                ];
+                for (a, b) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+                    ui.painter()
+                        .line_segment([center + *a, center + *b], stroke);
+                }
+                ui.painter().text(
+                    center + vec2(SIZE + 2., 0.),
+                    Align2::LEFT_CENTER,
+                    format!("L{}", i + 1),
+                    FontId::proportional(11.),
+                    palette.lagrange,
+                );
+            }
+        }
+
+        if let Some(problem) = self.cr3bp.filter(|_| self.show_zero_velocity_curves) {
+            let elapsed = self.simulation.elapsed_seconds();
+            let stroke = Stroke::new(1., palette.lagrange);
+            for segment in problem.zero_velocity_curves(self.jacobi_constant, 1.6, 160) {
+                let [a, b] = segment.map(|point| {
+                    let (position, _) = problem.from_rotating(elapsed, point, DVec2::default());
+                    screen_position(transform, self.projection.apply_inclined(position, 0.))
+                });
+                ui.painter().line_segment([a, b], stroke);
+            }
         }
 
-        Default::default()
+        if self.show_barycenter {
+            let center = self.projection.screen_position(transform, barycenter);
+            let stroke = Stroke::new(1., palette.highlight);
+            const ARM: f32 = 6.;
+            ui.painter()
+                .line_segment([center - vec2(ARM, 0.), center + vec2(ARM, 0.)], stroke);
+            ui.painter()
+                .line_segment([center - vec2(0., ARM), center + vec2(0., ARM)], stroke);
+        }
     }
-}
 
-impl eframe::App for App {
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    /// The body nearest `pointer`, if it is over the body's marker or within a few points of it.
+    fn body_under(
+        &self,
+        transform: &PlotTransform,
+        markers: MarkerSizing,
+        pointer: Pos2,
+    ) -> Option<BodyId> {
+        self.simulation
+            .bodies()
+            .iter()
+            .map(|body| {
+                let center = self.projection.body_screen_position(transform, body);
+                let gap = (center - pointer).length() - marker_radius(body, markers);
+                (body, gap)
+            })
+            .filter(|&(_, gap)| gap < 5.)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(body, _)| body.id)
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default()
-            .frame(egui::containers::Frame::default().inner_margin(Margin::ZERO))
+    /// Rings and labels the bodies' markers, and draws their velocity arrows. Returns whether the
+    /// selected body is flashing, for being within its primary's Roche limit.
+    fn draw_bodies(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        markers: MarkerSizing,
+        colors: &[Color32],
+        hovered: Option<BodyId>,
+    ) -> bool {
+        let palette = Palette::new(self.theme);
+        // The selected body, while it is within the Roche limit of its primary.
+        let disrupted = self
+            .selected()
+            .filter(|body| {
+                self.simulation
+                    .index_of(body.id)
+                    .and_then(|index| self.simulation.primary_of(index))
+                    .map(|primary| &self.simulation.bodies()[primary])
+                    .is_some_and(|primary| {
+                        let distance = (body.position - primary.position).length();
+                        distance < roche_limit(primary, body)
+                    })
+            })
+            .map(|body| body.id);
+        // On for half of every second.
+        let flash = ui.input(|i| i.time).fract() < 0.5;
+        for (body, &body_color) in self.simulation.bodies().iter().zip(colors) {
+            let highlighted = self.is_selected(body);
+            let is_hovered = hovered == Some(body.id);
+            let body_radius = marker_radius(body, markers);
+            let center = self.projection.body_screen_position(transform, body);
+            const HIGHLIGHT_RADIUS: f32 = 2.;
+            let is_disrupted = disrupted == Some(body.id);
+            let (color, stroke_width, font_size) = if is_disrupted && flash {
+                (Color32::RED, HIGHLIGHT_RADIUS, 16.)
+            } else if highlighted {
+                (palette.highlight, HIGHLIGHT_RADIUS, 16.)
+            } else if is_hovered {
+                (body_color, HIGHLIGHT_RADIUS, 14.)
+            } else {
+                (palette.text, 0.5, 12.)
+            };
+            ui.painter()
+                .circle_stroke(center, body_radius, Stroke::new(stroke_width, color));
+            if self.show_labels || highlighted || is_hovered {
+                ui.painter().text(
+                    center + vec2(body_radius + HIGHLIGHT_RADIUS + 3., -1.),
+                    Align2::LEFT_CENTER,
+                    body.name.as_str(),
+                    FontId::proportional(font_size),
+                    color,
+                );
+            }
+            let arrow = match self.velocity_arrows {
+                VelocityArrows::Off => false,
+                VelocityArrows::Selected => highlighted,
+                VelocityArrows::All => true,
+            };
+            if arrow {
+                // Screen y points down.
+                let velocity = self
+                    .projection
+                    .frame
+                    .apply_velocity(body.velocity)
+                    .to_vec2()
+                    * vec2(1., -1.);
+                draw_arrow(
+                    ui.painter(),
+                    center,
+                    velocity / 1e3 * self.arrow_scale,
+                    Stroke::new(1.5, body_color),
+                );
+            }
+        }
+        disrupted.is_some()
+    }
+
+    /// Measures between, selects or frames the body clicked or double-clicked, if any, and
+    /// selects the bodies boxed in.
+    fn plot_clicks(
+        &mut self,
+        ui: &Ui,
+        response: &Response,
+        transform: &PlotTransform,
+        markers: MarkerSizing,
+        click: Option<Pos2>,
+        double_click: Option<Pos2>,
+    ) {
+        let clicked_body = click.and_then(|click| self.body_under(transform, markers, click));
+        let framed =
+            double_click.and_then(|double_click| self.body_under(transform, markers, double_click));
+        let shift = ui.input(|i| i.modifiers.shift);
+        match clicked_body {
+            Some(body) if self.measuring => {
+                let [first, second] = self
+                    .measurement
+                    .map(|id| id.and_then(|id| self.simulation.body(id)));
+                match (first, second) {
+                    (Some(first), None) if first.id != body => {
+                        self.measurement[1] = Some(body);
+                    }
+                    (Some(_), None) => {}
+                    _ => self.measurement = [Some(body), None],
+                }
+            }
+            Some(body) if shift => self.toggle_selected(body),
+            Some(body) => self.selection = vec![body],
+            None if click.is_some() && !shift && !self.measuring => {
+                self.selection.clear();
+                self.follow = false;
+            }
+            None => {}
+        }
+        if let Some(body) = framed {
+            self.frame(body, response.rect.size());
+        }
+        let palette = Palette::new(self.theme);
+        self.box_select(ui, response, transform, shift, &palette);
+    }
+
+    /// The date and time, the measurement, the legend of the colors, the scale bar and the hint
+    /// for measuring, drawn over the plot.
+    fn plot_annotations(
+        &self,
+        ui: &Ui,
+        response: &Response,
+        transform: &PlotTransform,
+        color_range: Option<[f64; 2]>,
+        exporting: bool,
+    ) {
+        let elapsed = self.simulation.elapsed_seconds();
+        let date = DateTime::from_julian_date(self.epoch + elapsed / SECONDS_PER_DAY);
+        // Exported images show the clock once, in the top left tile.
+        let first_tile = self
+            .image_export
+            .as_ref()
+            .map_or(true, |export| export.tile == 0);
+        let palette = Palette::new(self.theme);
+        if first_tile {
+            ui.painter().text(
+                response.rect.left_top() + vec2(8., 8.),
+                Align2::LEFT_TOP,
+                format!("{date}\n{}", format_elapsed(elapsed)),
+                FontId::monospace(12.),
+                palette.text,
+            );
+        }
+        if let [Some(a), Some(b)] = self
+            .measurement
+            .map(|id| id.and_then(|id| self.simulation.body(id)))
+        {
+            let [start, end] =
+                [a, b].map(|body| self.projection.body_screen_position(transform, body));
+            let distance_m = (b.position - a.position).length();
+            ui.painter()
+                .line_segment([start, end], Stroke::new(1., palette.measurement));
+            ui.painter().text(
+                start + (end - start) * 0.5 + vec2(0., -4.),
+                Align2::CENTER_BOTTOM,
+                self.units.distance(distance_m),
+                FontId::monospace(12.),
+                palette.measurement,
+            );
+        }
+        if let Some(range) = color_range {
+            draw_legend(
+                ui.painter(),
+                response.rect.right_bottom() + vec2(-12., -12.),
+                self.color_by.name(),
+                range.map(|value| self.color_by.format(value, self.units)),
+                palette.text,
+            );
+        }
+        // Distances on a logarithmic projection have no single scale.
+        if !self.projection.log_distances {
+            draw_scale_bar(
+                ui.painter(),
+                response.rect.left_bottom() + vec2(12., -12.),
+                transform.dpos_dvalue_x() as f32,
+                self.units,
+                palette.text,
+            );
+        }
+        if self.measuring && !exporting {
+            ui.painter().text(
+                response.rect.left_bottom() + vec2(8., -36.),
+                Align2::LEFT_BOTTOM,
+                "Click two bodies to measure between them. Esc to clear.",
+                FontId::proportional(12.),
+                palette.text,
+            );
+        }
+    }
+
+    /// The name of `body`, under the pointer, with its distance from the heaviest body and its
+    /// speed.
+    fn body_tooltip(&self, ui: &Ui, body: BodyId) {
+        let Some(body) = self.simulation.body(body) else {
+            return;
+        };
+        let star = self
+            .simulation
+            .bodies()
+            .iter()
+            .max_by(|a, b| a.mass_kg.total_cmp(&b.mass_kg))
+            .filter(|star| star.id != body.id);
+        egui::show_tooltip_at_pointer(ui.ctx(), ui.layer_id(), Id::new("body_tooltip"), |ui| {
+            ui.strong(body.name.as_str());
+            if let Some(star) = star {
+                let distance = (body.position - star.position).length();
+                ui.label(format!(
+                    "{} from {}",
+                    self.units.distance(distance),
+                    star.name
+                ));
+            }
+            let velocity = self.projection.frame.apply_velocity(body.velocity);
+            ui.label(self.units.speed(velocity.length()));
+        });
+    }
+
+    /// Shows the prompt for a file's path, if open, acting on the file once given.
+    fn file_prompt_dialog(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.file_prompt else {
+            return;
+        };
+        let mut open = true;
+        let mut run = false;
+        Window::new(action.title())
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0., 0.])
             .show(ctx, |ui| {
-                let click = ui.get_click();
-                let body_radius = 10.;
-                let plot = Plot::new("main_plot")
-                    .show_grid(false)
-                    .show_axes(false)
-                    .data_aspect(1.0)
-                    .label_formatter(|_, _| "".to_string())
-                    .cursor_color(Color32::TRANSPARENT)
-                    .show(ui, |ui| {
-                        for Body {
-                            name,
-                            position,
-                            color,
-                            ..
-                        } in self.bodies.iter().map(|rc| &**rc)
-                        {
-                            ui.add(
-                                Points::new(PlotPoints::new(vec![[
-                                    position.x as f64,
-                                    position.y as f64,
-                                ]]))
-                                .color(*color)
-                                .radius(body_radius)
-                                .name(name)
-                                .id(Id::new(name)),
-                            );
-                            let radius = position.length() as f64;
-                            ui.add(
-                                Line::new(PlotPoints::new(
-                                    (0..=360)
-                                        .filter(|x| *x % 2 == 0)
-                                        .map(|deg| (deg as f64).to_radians())
-                                        .map(|rad| [radius * rad.cos(), radius * rad.sin()])
-                                        .collect::<Vec<_>>(),
-                                ))
-                                .style(LineStyle::Dotted { spacing: 4. })
-                                .color(*color)
-                                .width(0.5),
-                            );
-                        }
-                    });
+                ui.label(format!("{} file path:", action.format()));
+                let path = ui.text_edit_singleline(self.path_mut(action));
+                run = path.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if action == FileAction::ExportImage {
+                    let size = ui.ctx().screen_rect().size() * ui.ctx().pixels_per_point();
+                    ui.add(
+                        egui::Slider::new(&mut self.image_scale, 1..=4)
+                            .text("Scale")
+                            .suffix("×"),
+                    )
+                    .on_hover_text(format!(
+                        "Up to {:.0} × {:.0} pixels",
+                        size.x * self.image_scale as f32,
+                        size.y * self.image_scale as f32,
+                    ));
+                }
+                run |= ui.button(action.title()).clicked();
+            });
+        if run {
+            self.run_file_action(action);
+        }
+        if run || !open {
+            self.file_prompt = None;
+        }
+    }
+
+    /// Shows the last error, if there is one, until it's dismissed.
+    fn error_window(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let mut dismissed = false;
+        Window::new("Error")
+            .anchor(Align2::RIGHT_BOTTOM, [-10., -40.])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+                dismissed = ui.button("Dismiss").clicked();
+            });
+        if dismissed {
+            self.error = None;
+        }
+    }
+
+    /// Shows the settings for placing a new body, while placing one.
+    fn placement_window(&mut self, ctx: &egui::Context) {
+        let can_snap = self.show_grid && !self.projection.log_distances;
+        let Some(placement) = &mut self.placement else {
+            return;
+        };
+        Window::new("Add body")
+            .anchor(Align2::RIGHT_TOP, [-10., 40.])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Click to place a body, or drag to launch it.");
+                Grid::new("new_body").show(ui, |ui| {
+                    ui.label("Mass:");
+                    ui.add(
+                        egui::DragValue::new(&mut placement.mass_earths)
+                            .range(1e-6..=1e6)
+                            .speed(0.1)
+                            .suffix(" x Earth"),
+                    );
+                    ui.end_row();
+                    ui.label("Color:");
+                    ui.color_edit_button_srgba(&mut placement.color);
+                    ui.end_row();
+                });
+                ui.add_enabled(
+                    can_snap,
+                    egui::Checkbox::new(&mut self.snap_to_grid, "Snap to grid"),
+                )
+                .on_disabled_hover_text("Turn on the grid in the View menu");
+            });
+    }
 
-                let mut clicked_on_body = false;
-                for body_rc in self.bodies.iter() {
-                    let highlighted = self
-                        .selected
-                        .upgrade()
-                        .map(|selected| Rc::ptr_eq(&selected, &body_rc))
-                        .unwrap_or_default();
-                    let Body { name, position, .. } = &**body_rc;
-                    let center = plot
-                        .transform
-                        .position_from_point(&PlotPoint::new(position.x as f64, position.y as f64));
-                    const HIGHLIGHT_RADIUS: f32 = 2.;
-                    let color = if highlighted {
-                        Color32::WHITE
-                    } else {
-                        Color32::LIGHT_GRAY
+    /// Shows the selected body's window, if one body is selected: editing it, its orbit, its
+    /// satellites and Lagrange points, and, for a spacecraft, its maneuvers.
+    fn body_window(&mut self, ctx: &egui::Context) {
+        let Some((id, title, color)) = self
+            .selected()
+            .map(|body| (body.id, body.name.clone(), body.color))
+        else {
+            return;
+        };
+        let mut delete = false;
+        let mut spawn = None;
+        // Identified by the body rather than its title, so that renaming keeps it in place.
+        Window::new(title)
+            .id(Id::new(("body", id)))
+            .frame(
+                egui::containers::Frame::window(&ctx.style())
+                    .stroke(Stroke::new(ctx.style().visuals.window_stroke.width, color)), // .fill(color.lerp_to_gamma(Color32::BLACK, 0.5)), // .inner_margin(Margin::ZERO), // .multiply_with_opacity(0.8),
+            )
+            .anchor(Align2::CENTER_TOP, [0., 10.])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(mut edited) = self.simulation.body(id).cloned() else {
+                    return;
+                };
+                let units = self.units;
+                let changed = Grid::new("properties")
+                    .show(ui, |ui| edit_body(ui, &mut edited, units))
+                    .inner;
+                if changed {
+                    let time = ui.input(|i| i.time);
+                    self.history
+                        .record_edit(&self.simulation, &self.selection, time);
+                    if let Some(body) = self.simulation.body_mut(id) {
+                        *body = edited;
+                    }
+                }
+                let Some(index) = self.simulation.index_of(id) else {
+                    return;
+                };
+                let body = &self.simulation.bodies()[index];
+                let primary = self
+                    .simulation
+                    .primary_of(index)
+                    .map(|primary| &self.simulation.bodies()[primary]);
+                let elements = primary.map(|primary| body.orbital_elements(primary));
+                ui.separator();
+                Grid::new("stats").show(ui, |ui| {
+                    let mut row = |label: &str, value: Option<String>| {
+                        ui.label(RichText::new(label));
+                        ui.label(RichText::new(value.unwrap_or("—".into())).monospace());
+                        ui.end_row();
                     };
-                    ui.painter().circle_stroke(
-                        center,
-                        body_radius,
-                        Stroke::new(if highlighted { HIGHLIGHT_RADIUS } else { 0.5 }, color),
+                    let velocity = self.projection.frame.apply_velocity(body.velocity);
+                    row("Speed:", Some(units.speed(velocity.length())));
+                    row("Orbiting:", primary.map(|primary| primary.name.clone()));
+                    row("Distance:", elements.map(|e| units.distance(e.distance_m)));
+                    row(
+                        "Roche limit:",
+                        primary.zip(elements).map(|(primary, e)| {
+                            let limit = roche_limit(primary, body);
+                            let inside = if e.distance_m < limit {
+                                " (inside)"
+                            } else {
+                                ""
+                            };
+                            format!("{}{inside}", units.distance(limit))
+                        }),
+                    );
+                    row(
+                        "Semi-major axis:",
+                        elements.map(|e| units.distance(e.semi_major_axis_m)),
+                    );
+                    row(
+                        "Eccentricity:",
+                        elements.map(|e| format!("{:.4}", e.eccentricity)),
                     );
-                    ui.painter().text(
-                        center + vec2(body_radius + HIGHLIGHT_RADIUS + 3., -1.),
-                        Align2::LEFT_CENTER,
-                        name,
-                        FontId::proportional(if highlighted { 16. } else { 12. }),
-                        color,
+                    row(
+                        "Period:",
+                        elements
+                            .and_then(|e| e.period_s)
+                            .map(|period_s| format_duration(period_s as f32)),
                     );
-                    if let Some(click) = click {
-                        if (center - click).length() < body_radius + 5. {
-                            self.selected = Rc::downgrade(body_rc);
-                            clicked_on_body = true;
+                    row(
+                        "Periapsis:",
+                        elements.map(|e| units.distance(e.periapsis_m())),
+                    );
+                    row(
+                        "Apoapsis:",
+                        elements
+                            .and_then(|e| e.apoapsis_m())
+                            .map(|apoapsis_m| units.distance(apoapsis_m)),
+                    );
+                    row(
+                        "Next periapsis:",
+                        elements
+                            .and_then(|e| e.time_to_periapsis_s())
+                            .map(|time_s| format!("in {}", format_duration(time_s as f32))),
+                    );
+                });
+                let satellites = self.simulation.satellites_of(index);
+                if !satellites.is_empty() {
+                    ui.separator();
+                    ui.label("Satellites:");
+                    for satellite in satellites {
+                        let satellite = &self.simulation.bodies()[satellite];
+                        let text = RichText::new(satellite.name.as_str()).color(satellite.color);
+                        if ui.selectable_label(false, text).clicked() {
+                            self.selection = vec![satellite.id];
                         }
                     }
                 }
-                if click.is_some() && !clicked_on_body {
-                    self.selected = Default::default();
+                if let Some(primary) = primary.filter(|_| self.show_lagrange_points) {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Test particle at:");
+                        for (i, point) in lagrange_points(primary, body).into_iter().enumerate() {
+                            if ui
+                                .button(format!("L{}", i + 1))
+                                .on_hover_text(
+                                    "Adds a massless particle there, turning with the orbit, \
+                                     to see whether it stays",
+                                )
+                                .clicked()
+                            {
+                                let velocity = corotating_velocity(primary, body, point);
+                                spawn = Some((
+                                    format!("{} L{}", body.name, i + 1),
+                                    point,
+                                    velocity,
+                                    body.inclination_deg,
+                                ));
+                            }
+                        }
+                    });
+                }
+                ui.separator();
+                self.spacecraft_controls(ui, id, elements);
+                ui.separator();
+                self.trajectory_controls(ui, id);
+                ui.separator();
+                if ui.button("Delete").clicked() {
+                    delete = true;
                 }
             });
-        if let Some(body) = self.selected.upgrade() {
-            let Body {
-                name,
-                mass_kg,
-                color,
-                ..
-            } = &*body;
-            Window::new(name)
-                .frame(
-                    egui::containers::Frame::window(&ctx.style())
-                        .stroke(Stroke::new(ctx.style().visuals.window_stroke.width, *color)), // .fill(color.lerp_to_gamma(Color32::BLACK, 0.5)), // .inner_margin(Margin::ZERO), // .multiply_with_opacity(0.8),
-                )
-                .anchor(Align2::CENTER_TOP, [0., 10.])
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    Grid::new("stats").show(ui, |ui| {
-                        ui.label(RichText::new("Mass:"));
-                        let earth_masses = mass_kg / EARTH_MASS_KG;
-                        ui.label(RichText::new(format!("{earth_masses:.1} x Earth")).monospace())
-                    });
+        if let Some((name, position, velocity, inclination_deg)) = spawn {
+            self.add_lagrange_particle(&name, position, velocity, inclination_deg);
+        }
+        if delete {
+            self.delete(&[id]);
+        }
+    }
+
+    /// Samples the conserved quantities, while they're shown, for their drift.
+    fn track_conservation(&mut self) {
+        if self.show_diagnostics || self.show_drift {
+            let elapsed = self.simulation.elapsed_seconds();
+            if self
+                .conservation_history
+                .back()
+                .map_or(true, |last| last.elapsed_s != elapsed)
+            {
+                if self.conservation_history.len() == Self::CONSERVATION_HISTORY_LENGTH {
+                    // The first sample stays, as the one drift is measured from.
+                    self.conservation_history.remove(1);
+                }
+                self.conservation_history
+                    .push_back(Conserved::of(&self.simulation));
+            }
+        } else {
+            self.conservation_history.clear();
+        }
+    }
+
+    /// The energy, momentum and angular momentum, and the plot of their drift.
+    fn diagnostics_window(&mut self, ctx: &egui::Context) {
+        let energy = self.simulation.energy();
+        let DVec2 { x: px, y: py } = self.simulation.momentum();
+        let angular_momentum = self.simulation.angular_momentum();
+        Window::new("Diagnostics")
+            .open(&mut self.show_diagnostics)
+            .anchor(Align2::LEFT_BOTTOM, [10., -40.])
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("diagnostics").show(ui, |ui| {
+                    let mut row = |label: &str, value: String| {
+                        ui.label(label);
+                        ui.label(RichText::new(value).monospace());
+                        ui.end_row();
+                    };
+                    row("Kinetic energy:", format!("{:.6e} J", energy.kinetic_j));
+                    row("Potential energy:", format!("{:.6e} J", energy.potential_j));
+                    row("Total energy:", format!("{:.6e} J", energy.total_j()));
+                    row("Momentum:", format!("({px:.3e}, {py:.3e}) kg m/s"));
+                    row(
+                        "Angular momentum:",
+                        format!("{angular_momentum:.6e} kg m²/s"),
+                    );
                 });
+                // Drift relative to the oldest sample, so that the plot shows the
+                // integrator's error rather than the quantities' magnitudes.
+                if let Some(first) = self.conservation_history.front() {
+                    ui.label("Relative drift, over years:");
+                    let drifts: Vec<_> = self
+                        .conservation_history
+                        .iter()
+                        .map(|sample| (sample.elapsed_s / YEAR_S as f64, sample.drift_since(first)))
+                        .collect();
+                    let line = |index: usize, name: &str| {
+                        let points: Vec<_> = drifts
+                            .iter()
+                            .map(|&(years, drift)| [years, drift[index]])
+                            .collect();
+                        Line::new(PlotPoints::new(points)).name(name)
+                    };
+                    Plot::new("conservation_plot")
+                        .height(120.)
+                        .width(320.)
+                        .legend(Legend::default())
+                        .show_axes([true, true])
+                        .allow_drag(false)
+                        .allow_zoom(false)
+                        .allow_scroll(false)
+                        .allow_boxed_zoom(false)
+                        .show(ui, |ui| {
+                            ui.line(line(0, "Energy"));
+                            ui.line(line(1, "Momentum"));
+                            ui.line(line(2, "Angular momentum"));
+                        });
+                }
+                ui.checkbox(&mut self.show_drift, "Show drift beside the time controls");
+            });
+    }
+}
+
+impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let now = ctx.input(|i| i.time);
+        // Hold everything still while the tiles of an image, or the frames of an animation, are
+        // being captured.
+        let exporting = self.image_export.is_some() || self.recording.is_some();
+        // Time away doesn't count: the clock starts afresh once focus returns.
+        let focused = !self.pause_when_unfocused || ctx.input(|i| i.focused);
+        self.advance(ctx, now, exporting, focused);
+        self.keyboard_shortcuts(ctx);
+        let simulation = &self.simulation;
+        self.selection
+            .retain(|&selected| simulation.body(selected).is_some());
+        if self.selected().is_none() {
+            self.follow = false;
+        }
+        self.menu_bar(ctx);
+        self.time_controls(ctx);
+        if self.show_chart {
+            self.update_chart();
+            egui::TopBottomPanel::bottom("chart")
+                .resizable(true)
+                .default_height(200.)
+                .show(ctx, |ui| self.chart_panel(ui));
+        } else {
+            self.chart.series.clear();
+        }
+        let flashing = self.central_panel(ctx, exporting);
+        // Input wakes egui up by itself, so only ask for more frames while something moves
+        // without it. Otherwise a paused simulation would keep a core busy redrawing.
+        let coasting = self.view_velocity.pan != Vec2::ZERO || self.view_velocity.zoom != 0.;
+        if (!self.paused && focused) || exporting || coasting {
+            ctx.request_repaint();
+        } else if flashing {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        // Keep windows out of exported images.
+        if exporting {
+            return;
+        }
+        self.file_prompt_dialog(ctx);
+        self.error_window(ctx);
+        self.placement_window(ctx);
+        self.elements_dialog(ctx);
+        self.date_dialog(ctx);
+        self.approach_dialog(ctx);
+        self.cr3bp_window(ctx);
+        self.horizons_paste_dialog(ctx);
+        self.tle_dialog(ctx);
+        self.trajectory_export_dialog(ctx);
+        self.animation_dialog(ctx);
+        self.events_window(ctx);
+        if self.selection.len() > 1 {
+            self.selection_window(ctx);
+        }
+        self.body_window(ctx);
+        self.track_conservation();
+        if self.show_diagnostics {
+            self.diagnostics_window(ctx);
+        }
+    }
+}
+
+/// Grid rows editing `body`'s mass, velocity and color in place, returning whether any changed.
+fn edit_body(ui: &mut Ui, body: &mut Body, units: UnitSystem) -> bool {
+    /// Keeps masses positive.
+    const MIN_MASS_KG: f64 = 1e-12 * EARTH_MASS_KG;
+    let mut changed = false;
+    ui.label("Name:");
+    changed |= ui.text_edit_singleline(&mut body.name).changed();
+    ui.end_row();
+
+    let (kg_per_unit, mass_suffix) = units.mass_unit();
+    let mut mass = body.mass_kg / kg_per_unit;
+    let speed = mass * 0.01;
+    ui.label("Mass:");
+    let drag = egui::DragValue::new(&mut mass)
+        .range(MIN_MASS_KG / kg_per_unit..=f64::MAX)
+        .speed(speed)
+        .custom_formatter(|value, _| format_number(value))
+        .suffix(format!(" {mass_suffix}"));
+    let piloted = body.spacecraft.is_some();
+    if ui
+        .add_enabled(!piloted, drag)
+        .on_disabled_hover_text("A spacecraft's mass is its dry mass and propellant")
+        .changed()
+    {
+        changed = true;
+        body.mass_kg = mass * kg_per_unit;
+    }
+    ui.end_row();
+
+    let (m_per_unit, distance_suffix) = units.distance_unit();
+    let mut position = body.position / m_per_unit;
+    // About the same step as 100,000 km.
+    let step = 1e8 / m_per_unit;
+    ui.label("Position:");
+    let moved = ui
+        .horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut position.x)
+                    .speed(step)
+                    .suffix(format!(" {distance_suffix}")),
+            )
+            .changed()
+                | ui.add(
+                    egui::DragValue::new(&mut position.y)
+                        .speed(step)
+                        .suffix(format!(" {distance_suffix}")),
+                )
+                .changed()
+        })
+        .inner;
+    if moved {
+        changed = true;
+        body.position = position * m_per_unit;
+    }
+    ui.end_row();
+
+    let (m_s_per_unit, speed_suffix) = units.speed_unit();
+    let mut velocity = body.velocity / m_s_per_unit;
+    // About the same step as 0.1 km/s.
+    let step = 100. / m_s_per_unit;
+    ui.label("Velocity:");
+    let moved = ui
+        .horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut velocity.x)
+                    .speed(step)
+                    .suffix(format!(" {speed_suffix}")),
+            )
+            .changed()
+                | ui.add(
+                    egui::DragValue::new(&mut velocity.y)
+                        .speed(step)
+                        .suffix(format!(" {speed_suffix}")),
+                )
+                .changed()
+        })
+        .inner;
+    if moved {
+        changed = true;
+        body.velocity = velocity * m_s_per_unit;
+    }
+    ui.end_row();
+
+    ui.label("Inclination:");
+    let drag = egui::DragValue::new(&mut body.inclination_deg)
+        .range(-90.0..=90.)
+        .speed(0.1)
+        .suffix("°");
+    changed |= ui
+        .add(drag)
+        .on_hover_text("Only tilts how the orbit is drawn")
+        .changed();
+    ui.end_row();
+
+    ui.label("Gravitates:");
+    changed |= ui
+        .checkbox(&mut body.gravitates, "")
+        .on_hover_text("Untick to make a test particle, pulled by the others but pulling on none")
+        .changed();
+    ui.end_row();
+
+    ui.label("Color:");
+    changed |= ui.color_edit_button_srgba(&mut body.color).changed();
+    ui.end_row();
+    changed
+}
+
+/// Grid lines at every multiple of `step` within the plot's bounds, or none.
+fn grid_marks(step: Option<f64>) -> impl Fn(GridInput) -> Vec<GridMark> {
+    move |GridInput {
+              bounds: (min, max), ..
+          }| {
+        let Some(step) = step else {
+            return Vec::new();
+        };
+        let [first, last] = [(min / step).ceil(), (max / step).floor()].map(|i| i as i64);
+        (first..=last)
+            .map(|i| GridMark {
+                value: i as f64 * step,
+                step_size: step,
+            })
+            .collect()
+    }
+}
+
+/// Handles "Add body" input on the plot: pressing places a body, dragging out from there sets its
+/// velocity (with a preview arrow), and releasing returns its position and velocity.
+fn place_body(
+    plot_ui: &mut PlotUi,
+    response: &Response,
+    view: &View,
+    projection: Projection,
+    units: UnitSystem,
+    snap: Option<f64>,
+    placement: &mut BodyPlacement,
+) -> Option<(DVec2, DVec2)> {
+    if !response.is_pointer_button_down_on() {
+        let start = placement.start.take()?;
+        let velocity = BodyPlacement::velocity(placement.drag);
+        return Some((
+            projection.frame.invert(start),
+            projection.frame.invert_velocity(velocity),
+        ));
+    }
+    let (origin, pointer) = response
+        .ctx
+        .input(|i| (i.pointer.press_origin(), i.pointer.interact_pos()));
+    let (origin, pointer) = (origin?, pointer?);
+    let start = *placement.start.get_or_insert_with(|| {
+        let start = projection.unproject(view.world_from_screen(origin, response.rect));
+        match snap {
+            Some(step) => dvec2(
+                (start.x / step).round() * step,
+                (start.y / step).round() * step,
+            ),
+            None => start,
+        }
+    });
+    placement.drag = pointer - origin;
+    let velocity = BodyPlacement::velocity(placement.drag);
+    let tail = projection.project(start, 0.);
+    let tip = view.world_from_screen(pointer, response.rect);
+    plot_ui.arrows(
+        Arrows::new(
+            vec![[tail.x as f64, tail.y as f64]],
+            vec![[tip.x as f64, tip.y as f64]],
+        )
+        .color(placement.color),
+    );
+    plot_ui.text(
+        Text::new(
+            PlotPoint::new(tip.x as f64, tip.y as f64),
+            format!(
+                "({}, {})\n{}",
+                units.distance(start.x),
+                units.distance(start.y),
+                units.speed(velocity.length())
+            ),
+        )
+        .anchor(Align2::LEFT_BOTTOM)
+        .color(placement.color),
+    );
+    None
+}
+
+/// Perceptually uniform colors from dark purple at `t = 0` through teal to yellow at `t = 1`,
+/// after matplotlib's viridis.
+fn colormap(t: f32) -> Color32 {
+    const STOPS: [[u8; 3]; 9] = [
+        [68, 1, 84],
+        [71, 44, 122],
+        [59, 81, 139],
+        [44, 113, 142],
+        [33, 144, 141],
+        [39, 173, 129],
+        [92, 200, 99],
+        [170, 220, 50],
+        [253, 231, 37],
+    ];
+    let position = t.clamp(0., 1.) * (STOPS.len() - 1) as f32;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let fraction = position - index as f32;
+    let [[r0, g0, b0], [r1, g1, b1]] = [STOPS[index], STOPS[index + 1]];
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color32::from_rgb(mix(r0, r1), mix(g0, g1), mix(b0, b1))
+}
+
+/// A bar of the [`colormap`] with its bottom right corner at `corner`, titled and labeled with
+/// the values at either end.
+fn draw_legend(
+    painter: &egui::Painter,
+    corner: Pos2,
+    title: &str,
+    [min, max]: [String; 2],
+    color: Color32,
+) {
+    const SIZE: Vec2 = vec2(160., 10.);
+    const SEGMENTS: usize = 32;
+    let bar = Rect::from_min_max(corner - SIZE, corner);
+    let segment_width = SIZE.x / SEGMENTS as f32;
+    for i in 0..SEGMENTS {
+        let left = bar.left() + i as f32 * segment_width;
+        painter.rect_filled(
+            Rect::from_x_y_ranges(left..=left + segment_width, bar.y_range()),
+            0.,
+            colormap((i as f32 + 0.5) / SEGMENTS as f32),
+        );
+    }
+    let font = FontId::proportional(11.);
+    let above = vec2(0., -3.);
+    painter.text(
+        bar.left_top() + above,
+        Align2::LEFT_BOTTOM,
+        min,
+        font.clone(),
+        color,
+    );
+    painter.text(
+        bar.right_top() + above,
+        Align2::RIGHT_BOTTOM,
+        max,
+        font.clone(),
+        color,
+    );
+    painter.text(
+        bar.center_top() + above * 6.,
+        Align2::CENTER_BOTTOM,
+        title,
+        font,
+        color,
+    );
+}
+
+/// Where the minimap goes in a plot filling `panel`: a square in its top right corner.
+fn minimap_rect(panel: Rect) -> Rect {
+    const SIZE: f32 = 160.;
+    const MARGIN: f32 = 12.;
+    let corner = panel.right_top() + vec2(-MARGIN, MARGIN);
+    Rect::from_min_max(corner - vec2(SIZE, 0.), corner + vec2(0., SIZE))
+}
+
+/// A bar, with its bottom left corner at `corner`, as long as a round distance in `units` at a
+/// zoom of `points_per_meter`.
+fn draw_scale_bar(
+    painter: &egui::Painter,
+    corner: Pos2,
+    points_per_meter: f32,
+    units: UnitSystem,
+    color: Color32,
+) {
+    /// About how long the bar is, in points.
+    const TARGET_LENGTH: f32 = 100.;
+    let (meters_per_unit, symbol) = units.distance_unit();
+    let target = (TARGET_LENGTH / points_per_meter) as f64 / meters_per_unit;
+    if !target.is_finite() || target <= 0. {
+        return;
+    }
+    let round = round_length(target);
+    let length = (round * meters_per_unit) as f32 * points_per_meter;
+    let stroke = Stroke::new(1., color);
+    let end = corner + vec2(length, 0.);
+    painter.line_segment([corner, end], stroke);
+    for x in [corner, end] {
+        painter.line_segment([x, x - vec2(0., 4.)], stroke);
+    }
+    let label = if (1e-3..1e6).contains(&round) {
+        format!("{round} {symbol}")
+    } else {
+        format!("{round:e} {symbol}")
+    };
+    painter.text(
+        corner + vec2(length / 2., -4.),
+        Align2::CENTER_BOTTOM,
+        label,
+        FontId::proportional(11.),
+        color,
+    );
+}
+
+/// The largest 1, 2 or 5 times a power of ten that is at most `target`.
+fn round_length(target: f64) -> f64 {
+    let magnitude = 10_f64.powi(target.log10().floor() as i32);
+    [5., 2., 1.]
+        .into_iter()
+        .map(|mantissa| mantissa * magnitude)
+        .find(|&round| round <= target)
+        .unwrap_or(magnitude)
+}
+
+/// A line from `origin` along `vector`, with a head of two short strokes at its tip.
+fn draw_arrow(painter: &egui::Painter, origin: Pos2, vector: Vec2, stroke: Stroke) {
+    let length = vector.length();
+    if length < 1. {
+        return;
+    }
+    let tip = origin + vector;
+    painter.line_segment([origin, tip], stroke);
+    let back = -vector / length * (length * 0.3).min(8.);
+    for angle in [-0.5, 0.5] {
+        painter.line_segment(
+            [tip, tip + egui::emath::Rot2::from_angle(angle) * back],
+            stroke,
+        );
+    }
+}
+
+/// Astronomical unit, in meters.
+const AU_M: f64 = 1.495_978_707e11;
+const MINUTE_S: f32 = 60.;
+const HOUR_S: f32 = 60. * MINUTE_S;
+const DAY_S: f32 = 24. * HOUR_S;
+const YEAR_S: f32 = 365.25 * DAY_S;
+
+fn format_duration(seconds: f32) -> String {
+    if seconds < MINUTE_S {
+        format!("{seconds:.0} seconds")
+    } else if seconds < HOUR_S {
+        format!("{:.1} minutes", seconds / MINUTE_S)
+    } else if seconds < DAY_S {
+        format!("{:.1} hours", seconds / HOUR_S)
+    } else if seconds < YEAR_S {
+        format!("{:.1} days", seconds / DAY_S)
+    } else {
+        format!("{:.2} years", seconds / YEAR_S)
+    }
+}
+
+/// Four significant figures, switching to scientific notation for very large or small values.
+fn format_number(value: f64) -> String {
+    if value == 0. {
+        "0".to_string()
+    } else if (1e-3..1e6).contains(&value.abs()) {
+        let decimals = (3 - value.abs().log10().floor() as i32).max(0) as usize;
+        format!("{value:.decimals$}")
+    } else {
+        format!("{value:.3e}")
+    }
+}
+
+fn format_elapsed(seconds: f64) -> String {
+    let since = if seconds < 0. {
+        "before the start"
+    } else {
+        "elapsed"
+    };
+    let seconds = seconds.abs();
+    let years = (seconds / YEAR_S as f64).floor();
+    let days = (seconds - years * YEAR_S as f64) / DAY_S as f64;
+    format!("{years:.0} years, {days:.0} days {since}")
+}
+
+/// On-screen radius of a body's marker, in points.
+fn marker_radius(body: &Body, sizing: MarkerSizing) -> f32 {
+    const FIXED_RADIUS: f32 = 10.;
+    const MIN_RADIUS: f32 = 4.;
+    /// Keeps a body drawn to scale visible when zoomed out.
+    const MIN_SCALE_RADIUS: f32 = 1.5;
+    match sizing.size {
+        BodySize::Fixed => FIXED_RADIUS,
+        BodySize::Logarithmic => (3. * body.radius_km.max(1.).log10() - 5.).max(MIN_RADIUS),
+        BodySize::ToScale => {
+            (body.radius_km * 1e3 * sizing.exaggeration / sizing.scale).max(MIN_SCALE_RADIUS)
         }
     }
 }
@@ -204,6 +5420,7 @@ impl eframe::App for App {
 trait UiExt {
     fn debug_rect(&mut self, rect: Rect);
     fn get_click(&mut self) -> Option<Pos2>;
+    fn get_double_click(&mut self) -> Option<Pos2>;
 }
 
 impl UiExt for Ui {
@@ -232,6 +5449,14 @@ impl UiExt for Ui {
             })
         })
     }
+    fn get_double_click(&mut self) -> Option<Pos2> {
+        self.ctx().input(|i| {
+            i.pointer
+                .button_double_clicked(PointerButton::Primary)
+                .then(|| i.pointer.interact_pos())
+                .flatten()
+        })
+    }
 }
 
 // trait Color32Ext {
@@ -254,3 +5479,17 @@ impl UiExt for Ui {
 //     let increase = headroom as f32 * amount;
 //     (value + increase as u8).min(255)
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_stalled_frame_advances_at_most_a_frame() {
+        let scale = App::DEFAULT_TIME_SCALE as f64;
+        let step = App::frame_step(10., 13.5, scale);
+        assert!(step > 0. && step <= App::MAX_FRAME_DT * scale);
+        assert!(App::frame_step(10., 13.5, -scale) >= -App::MAX_FRAME_DT * scale);
+        assert!((App::frame_step(10., 10.01, 2.) - 0.02).abs() < 1e-9);
+    }
+}
@@ -1,4 +1,5 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+// The physics lives in the `aetherweave-core` crate, so it can be driven without the GUI.
 mod app;
 pub use app::App;
@@ -1,11 +1,24 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+#[cfg(not(target_arch = "wasm32"))]
+mod sim;
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("sim") {
+        attach_to_parent_console();
+        if let Err(error) = sim::run(args) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -24,6 +37,26 @@ fn main() -> eframe::Result {
     )
 }
 
+/// A release build on Windows has no console of its own, so without this the `sim` subcommand's
+/// output and errors would go nowhere. Borrows the console of the shell it was run from, if any.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_to_parent_console() {
+    /// `ATTACH_PARENT_PROCESS`, as a `DWORD`.
+    const PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Failing only means it wasn't run from a console, and there's nowhere to show the output.
+    // SAFETY: `AttachConsole` takes a process id and only changes the process's console.
+    unsafe {
+        AttachConsole(PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", all(windows, not(debug_assertions)))))]
+fn attach_to_parent_console() {}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
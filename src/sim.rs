@@ -0,0 +1,192 @@
+//! `aetherweave sim`: runs a system from a file with no window, writing the bodies' states as
+//! CSV.
+
+use aetherweave_core::{
+    calendar::SECONDS_PER_DAY, load_system, save_system, trajectory::csv_field, Integrator,
+    Simulation,
+};
+use std::{fmt::Write as _, path::PathBuf};
+
+const USAGE: &str = "\
+Usage: aetherweave sim <system.ron> --duration <time> [options]
+
+Runs the system in the file for the given time and writes the state of every body, as CSV, to
+standard output or to --output. Times are in seconds, or with a unit: 90min, 12h, 30d, 10y.
+
+Options:
+    --duration <time>       How long to simulate for. Negative runs backwards.
+    --every <time>          Also write the states this often along the way.
+    --step <time>           Length of each physics step [default: 1h].
+    --adaptive              Pick each step's length from the closest encounter instead.
+    --integrator <name>     euler, semiimpliciteuler, verlet, leapfrog or rk4 [default: verlet].
+    --output <file.csv>     Where to write the states.
+    --save <file.ron>       Where to save the final system.";
+
+struct Options {
+    system: PathBuf,
+    duration_s: f64,
+    every_s: Option<f64>,
+    step_s: Option<f64>,
+    adaptive: bool,
+    integrator: Integrator,
+    output: Option<PathBuf>,
+    save: Option<PathBuf>,
+}
+
+/// Runs the `sim` subcommand with the arguments after it.
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let options = parse(args)?;
+    let mut simulation = load_system(&options.system)
+        .map_err(|error| format!("{}: {error}", options.system.display()))?;
+    if let Some(step_s) = options.step_s {
+        simulation.timestep_s = step_s;
+    }
+    simulation.adaptive_timestep = options.adaptive;
+    // Trails are only for drawing.
+    simulation.trail_duration_s = 0.;
+    let mut csv = String::from("time_s,body,x_m,y_m,vx_m_s,vy_m_s\n");
+    let (direction, duration_s) = (options.duration_s.signum(), options.duration_s.abs());
+    let interval_s = options.every_s.unwrap_or(duration_s);
+    if options.every_s.is_some() && duration_s > 0. {
+        write_states(&mut csv, &simulation);
+    }
+    let mut done_s = 0.;
+    for sample in 1_u64.. {
+        // Counted from the start, so that rounding doesn't pile up from one sample to the next.
+        let sample_s = (sample as f64 * interval_s).min(duration_s);
+        simulation.run_for(direction * (sample_s - done_s), options.integrator);
+        done_s = sample_s;
+        write_states(&mut csv, &simulation);
+        if done_s >= duration_s {
+            break;
+        }
+    }
+    match &options.output {
+        Some(path) => std::fs::write(path, csv)
+            .map_err(|error| format!("Couldn't write {}: {error}", path.display()))?,
+        None => print!("{csv}"),
+    }
+    if let Some(path) = &options.save {
+        save_system(path, &simulation).map_err(|error| format!("{}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut system = None;
+    let mut duration_s = None;
+    let mut options = Options {
+        system: PathBuf::new(),
+        duration_s: 0.,
+        every_s: None,
+        step_s: None,
+        adaptive: false,
+        integrator: Integrator::default(),
+        output: None,
+        save: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{arg} needs a value\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--duration" => duration_s = Some(parse_time(&value()?)?),
+            "--every" => {
+                let every_s = parse_time(&value()?)?;
+                if every_s <= 0. {
+                    return Err("--every must be more than zero".to_string());
+                }
+                options.every_s = Some(every_s);
+            }
+            "--step" => {
+                let step_s = parse_time(&value()?)?;
+                if step_s <= 0. {
+                    return Err("--step must be more than zero".to_string());
+                }
+                options.step_s = Some(step_s);
+            }
+            "--adaptive" => options.adaptive = true,
+            "--integrator" => {
+                let name = value()?.to_lowercase();
+                options.integrator = Integrator::ALL
+                    .into_iter()
+                    .find(|integrator| format!("{integrator:?}").to_lowercase() == name)
+                    .ok_or_else(|| format!("Unknown integrator \"{name}\"\n\n{USAGE}"))?;
+            }
+            "--output" => options.output = Some(value()?.into()),
+            "--save" => options.save = Some(value()?.into()),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}\n\n{USAGE}")),
+            _ if system.is_none() => system = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument \"{arg}\"\n\n{USAGE}")),
+        }
+    }
+    options.system = system.ok_or_else(|| USAGE.to_string())?;
+    options.duration_s = duration_s.ok_or_else(|| format!("--duration is needed\n\n{USAGE}"))?;
+    Ok(options)
+}
+
+/// Seconds in `text`, a number followed by an optional unit.
+fn parse_time(text: &str) -> Result<f64, String> {
+    const UNITS: [(&str, f64); 6] = [
+        ("min", 60.),
+        ("s", 1.),
+        ("h", 3_600.),
+        ("d", SECONDS_PER_DAY),
+        // Julian years.
+        ("y", 365.25 * SECONDS_PER_DAY),
+        ("", 1.),
+    ];
+    let (number, seconds_per_unit) = UNITS
+        .iter()
+        .find_map(|&(unit, seconds)| Some((text.strip_suffix(unit)?, seconds)))
+        .unwrap_or((text, 1.));
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Couldn't read \"{text}\" as a time"))?;
+    // `parse` takes "nan" and "inf", which would never be reached.
+    let seconds = value * seconds_per_unit;
+    if !seconds.is_finite() {
+        return Err(format!("\"{text}\" isn't a finite time"));
+    }
+    Ok(seconds)
+}
+
+/// A row per body, at the simulation's current time.
+fn write_states(csv: &mut String, simulation: &Simulation) {
+    for body in simulation.bodies() {
+        let (position, velocity) = (body.position, body.velocity);
+        let name = csv_field(&body.name);
+        // Writing to a `String` can't fail.
+        let _ = writeln!(
+            csv,
+            "{},{name},{},{},{},{}",
+            simulation.elapsed_seconds(),
+            position.x,
+            position.y,
+            velocity.x,
+            velocity.y
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_reads_units_and_rejects_non_finite_times() {
+        assert_eq!(parse_time("90"), Ok(90.));
+        assert_eq!(parse_time("90s"), Ok(90.));
+        // Not 90 months, nor "90mi" seconds.
+        assert_eq!(parse_time("90min"), Ok(5_400.));
+        assert_eq!(parse_time("12h"), Ok(43_200.));
+        assert_eq!(parse_time("-2d"), Ok(-2. * SECONDS_PER_DAY));
+        assert_eq!(parse_time("1y"), Ok(365.25 * SECONDS_PER_DAY));
+        for text in ["nan", "inf", "-inf", "NaNs", "infd", "1e308y", "soon"] {
+            assert!(parse_time(text).is_err(), "{text} was read as a time");
+        }
+    }
+}